@@ -56,10 +56,10 @@ pub fn bench(id: &str, iters: usize, bufsize: usize) -> Duration {
     let id = handle.prepare(CAP).expect("alloc ok");
     let view = handle.acquire(id).expect("view ok");
 
-    let (ls, lr) = view.clone().lsplit();
-    let (rs, rr) = view.clone().rsplit();
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
 
-    let (ls, lr) = CachePool::<(), CAP>::new().bind(ls, lr);
+    let (ls, lr) = CachePool::<()>::new(CAP).bind(ls, lr, handle.alloc.clone());
 
     std::thread::scope(|s| {
         let salloc = handle.alloc.clone();
@@ -111,7 +111,7 @@ pub fn bench(id: &str, iters: usize, bufsize: usize) -> Duration {
             runtime.block_on(async move {
                 tokio::spawn(async move {
                     loop {
-                        match lr.complete() {
+                        match lr.complete_many(usize::MAX) {
                             Ok(_) => continue,
                             Err(TryRecvError::Empty) => {
                                 tokio::task::yield_now().await;
@@ -139,15 +139,19 @@ pub fn bench(id: &str, iters: usize, bufsize: usize) -> Duration {
                                         tokio::task::yield_now().await;
                                         continue;
                                     }
-                                    Err(TrySubmitError::SendError(TrySendError::Disconnected)) => {
+                                    Err(TrySubmitError::SendError(TrySendError::Disconnected))
+                                    | Err(TrySubmitError::Aborted(_)) => {
                                         break;
                                     }
-                                    Err(TrySubmitError::CacheFull) => {
+                                    Err(TrySubmitError::CacheFull(_)) => {
                                         tokio::task::yield_now().await;
                                         continue;
                                     }
                                 };
-                                let (resp, _) = op.await.unpack();
+                                let Ok(resp) = op.await else {
+                                    break;
+                                };
+                                let (resp, _) = resp.unpack();
                                 let resp = MoveMsg::detoken(resp, alloc).expect("should detoken");
 
                                 check_resp(bufsize, &resp);
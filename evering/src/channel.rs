@@ -1,12 +1,31 @@
+use alloc::vec::Vec;
 use core::cell::UnsafeCell;
 use core::mem::MaybeUninit;
-use core::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
+use core::ops::ControlFlow;
+use core::sync::atomic::{self, AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use core::task::{Context, Poll};
 
 use crossbeam_utils::{Backoff, CachePadded};
 
+pub mod broadcast;
 pub mod cross;
+#[cfg(all(feature = "tokio", target_os = "linux"))]
+pub mod doorbell;
 pub mod driver;
-mod local;
+mod guard;
+pub mod local;
+mod notify;
+mod resize;
+pub mod rpc;
+pub mod select;
+mod spsc;
+mod stats;
+
+pub use self::guard::{ReadGuard, SlotGuard};
+pub use self::notify::Notify;
+use self::resize::Side;
+#[cfg(feature = "stats")]
+pub use self::stats::QueueStats;
 
 // type Slots<T> = [Slot<T>];
 /// A slot in a queue.
@@ -19,8 +38,38 @@ pub struct Slot<T> {
 
     /// The value in this slot.
     value: UnsafeCell<MaybeUninit<T>>,
+
+    /// Set if the slot was reserved and released without commit, it holds no value and is
+    /// skipped by consumers.
+    abandoned: AtomicBool,
+}
+
+impl<T> Slot<T> {
+    /// A vacant slot, next written to at `{ lap: 0, index }`.
+    #[inline]
+    fn vacant(index: usize) -> Self {
+        Slot {
+            stamp: AtomicUsize::new(index),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            abandoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Reads the value out of a claimed slot, unless its reservation was released.
+    #[inline]
+    fn take(&self) -> Option<T> {
+        if self.abandoned.load(Ordering::Relaxed) {
+            self.abandoned.store(false, Ordering::Relaxed);
+            return None;
+        }
+        Some(unsafe { self.value.get().read().assume_init() })
+    }
 }
 
+/// The shared state of a queue, in front of its slots.
+///
+/// The layout does not depend on the enabled features, so that processes built with different
+/// ones still share a queue.
 pub struct Header {
     /// The head of the queue.
     ///
@@ -39,17 +88,198 @@ pub struct Header {
     tail: CachePadded<AtomicUsize>,
 
     /// A stamp with the value of `{ lap: 1, index: 0 }`.
-    one_lap: usize,
+    ///
+    /// Like the capacity, it only changes while the queue is quiesced for a resize.
+    one_lap: AtomicUsize,
 
     /// The queue capacity.
-    cap: usize,
+    cap: AtomicUsize,
 
     /// The disconnection.
     close: AtomicBool,
+
+    /// Receivers parked on an empty queue, notified on push.
+    readable: Notify,
+
+    /// Senders parked on a full queue, notified on pop.
+    writable: Notify,
+
+    /// The access pattern the queue is specialized for.
+    mode: Mode,
+
+    /// The last head observed by the producer in SPSC mode.
+    head_cache: CachePadded<AtomicUsize>,
+
+    /// The last tail observed by the consumer in SPSC mode.
+    tail_cache: CachePadded<AtomicUsize>,
+
+    /// The attached senders, the queue is closed when the last one drops.
+    senders: Attached,
+
+    /// The attached receivers, the queue is closed when the last one drops.
+    receivers: Attached,
+
+    /// The traffic counters, only maintained with the `stats` feature.
+    #[cfg_attr(not(feature = "stats"), allow(dead_code))]
+    stats: stats::Stats,
+
+    /// The operations in progress on either side, waited out by a resize, only maintained with
+    /// the `resize` feature.
+    #[cfg_attr(not(feature = "resize"), allow(dead_code))]
+    active: resize::Active,
+}
+
+/// The processes tracked per side, the endpoints of further ones are counted but never probed.
+const PIDS: usize = 8;
+
+/// Endpoints attached to one side of a queue.
+struct Attached {
+    count: AtomicU32,
+    /// The processes holding endpoints, each packed as `pid << 32 | endpoints`, zero if free.
+    pids: [AtomicU64; PIDS],
+}
+
+impl Attached {
+    const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+            pids: [const { AtomicU64::new(0) }; PIDS],
+        }
+    }
+
+    /// Attaches an endpoint, unless `exclusive` and one is attached already.
+    ///
+    /// Returns `true` if attached.
+    fn attach(&self, exclusive: bool) -> bool {
+        if exclusive {
+            // Pairs with the release on detach: the new endpoint picks up where the previous
+            // one has left the indices.
+            if self
+                .count
+                .compare_exchange(0, 1, Ordering::AcqRel, Ordering::Relaxed)
+                .is_err()
+            {
+                return false;
+            }
+        } else {
+            self.count.fetch_add(1, Ordering::AcqRel);
+        }
+        self.track(process_id());
+        true
+    }
+
+    /// Returns `true` if the last endpoint is detached.
+    fn detach(&self) -> bool {
+        self.untrack(process_id());
+        self.count.fetch_sub(1, Ordering::AcqRel) == 1
+    }
+
+    /// Counts an endpoint held by `pid`, in the entry of the process or else in a free one.
+    fn track(&self, pid: u32) {
+        if pid == 0 {
+            return;
+        }
+        let tag = u64::from(pid) << 32;
+        for free in [false, true] {
+            for entry in &self.pids {
+                let mut cur = entry.load(Ordering::Relaxed);
+                loop {
+                    let next = match cur {
+                        0 if free => tag | 1,
+                        _ if cur != 0 && cur & !u64::from(u32::MAX) == tag => cur + 1,
+                        _ => break,
+                    };
+                    match entry.compare_exchange_weak(
+                        cur,
+                        next,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => return,
+                        Err(v) => cur = v,
+                    }
+                }
+            }
+        }
+        // Every entry is taken, the endpoint is never probed.
+    }
+
+    /// Forgets an endpoint held by `pid`, if tracked.
+    fn untrack(&self, pid: u32) {
+        if pid == 0 {
+            return;
+        }
+        let tag = u64::from(pid) << 32;
+        for entry in &self.pids {
+            let mut cur = entry.load(Ordering::Relaxed);
+            while cur != 0 && cur & !u64::from(u32::MAX) == tag {
+                let next = if cur == tag | 1 { 0 } else { cur - 1 };
+                match entry.compare_exchange_weak(cur, next, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => return,
+                    Err(v) => cur = v,
+                }
+            }
+        }
+    }
+
+    fn reset(&self) {
+        self.count.store(0, Ordering::Relaxed);
+        for entry in &self.pids {
+            entry.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Detaches the endpoints of the tracked processes known to be gone.
+    ///
+    /// Returns `true` if the last endpoint is detached.
+    #[cfg(feature = "unix")]
+    fn reap(&self) -> bool {
+        let mut last = false;
+        for entry in &self.pids {
+            let cur = entry.load(Ordering::Relaxed);
+            if cur == 0 || crate::os::unix::process_alive((cur >> 32) as u32) {
+                continue;
+            }
+            // A gone process never updates its entry again, only a racing reaper does.
+            if entry
+                .compare_exchange(cur, 0, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+            {
+                let n = cur as u32;
+                last |= self.count.fetch_sub(n, Ordering::AcqRel) == n;
+            }
+        }
+        last
+    }
+}
+
+#[inline]
+fn process_id() -> u32 {
+    #[cfg(feature = "std")]
+    {
+        std::process::id()
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0
+    }
+}
+
+/// The access pattern a queue is specialized for, fixed at creation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Mode {
+    /// Any number of producers and consumers, each slot is claimed by CAS.
+    #[default]
+    Mpmc,
+    /// Exactly one producer and one consumer, indices are published by plain stores.
+    ///
+    /// Attaching a second sender or receiver, e.g. by cloning, panics.
+    Spsc,
 }
 
 impl Header {
-    const fn new(cap: usize) -> Self {
+    const fn new(cap: usize, mode: Mode) -> Self {
         assert!(cap > 0, "capacity must not zero");
         // Head is initialized to `{ lap: 0, index: 0 }`.
         // Tail is initialized to `{ lap: 0, index: 0 }`.
@@ -60,14 +290,26 @@ impl Header {
         Header {
             head: CachePadded::new(AtomicUsize::new(head)),
             tail: CachePadded::new(AtomicUsize::new(tail)),
-            one_lap,
-            cap,
+            one_lap: AtomicUsize::new(one_lap),
+            cap: AtomicUsize::new(cap),
             close: AtomicBool::new(false),
+            readable: Notify::new(),
+            writable: Notify::new(),
+            mode,
+            head_cache: CachePadded::new(AtomicUsize::new(head)),
+            tail_cache: CachePadded::new(AtomicUsize::new(tail)),
+            senders: Attached::new(),
+            receivers: Attached::new(),
+            stats: stats::Stats::new(),
+            active: resize::Active::new(),
         }
     }
 
     pub fn close(&self) {
-        self.close.store(true, Ordering::Release)
+        self.close.store(true, Ordering::Release);
+        // Parked peers must observe the disconnection.
+        self.readable.notify_all();
+        self.writable.notify_all();
     }
 
     pub fn open(&self) {
@@ -77,6 +319,172 @@ impl Header {
     pub fn is_close(&self) -> bool {
         self.close.load(Ordering::Relaxed)
     }
+
+    /// Reopens the queue and forgets the attached endpoints.
+    fn reset(&self) {
+        self.senders.reset();
+        self.receivers.reset();
+        self.open();
+    }
+
+    /// Attaches a sender, reopening the queue closed meanwhile if receivers are attached.
+    ///
+    /// Fails if the queue is in SPSC mode and a sender is attached already, in any process.
+    #[inline]
+    fn attach_sender(&self) -> Result<(), AttachError> {
+        if !self.senders.attach(self.mode == Mode::Spsc) {
+            return Err(AttachError);
+        }
+        self.rejoin();
+        Ok(())
+    }
+
+    /// Attaches a receiver, reopening the queue closed meanwhile if senders are attached.
+    ///
+    /// Fails if the queue is in SPSC mode and a receiver is attached already, in any process.
+    #[inline]
+    fn attach_receiver(&self) -> Result<(), AttachError> {
+        if !self.receivers.attach(self.mode == Mode::Spsc) {
+            return Err(AttachError);
+        }
+        self.rejoin();
+        Ok(())
+    }
+
+    /// Detaches a sender, closing the queue if it is the last one.
+    #[inline]
+    fn detach_sender(&self) {
+        if self.senders.detach() {
+            self.close();
+            // A sender attached before the close may have found the queue open.
+            self.rejoin();
+        }
+    }
+
+    /// Detaches a receiver, closing the queue if it is the last one.
+    #[inline]
+    fn detach_receiver(&self) {
+        if self.receivers.detach() {
+            self.close();
+            // A receiver attached before the close may have found the queue open.
+            self.rejoin();
+        }
+    }
+
+    /// Reopens the closed queue if both sides have endpoints attached again.
+    ///
+    /// A side emptied while reopening closes the queue once more, so that the last one to
+    /// attach or detach settles the state.
+    fn rejoin(&self) {
+        atomic::fence(Ordering::SeqCst);
+        if !self.is_close() || self.senders() == 0 || self.receivers() == 0 {
+            return;
+        }
+        self.open();
+        atomic::fence(Ordering::SeqCst);
+        if self.senders() == 0 || self.receivers() == 0 {
+            self.close();
+        }
+    }
+
+    /// Returns the number of attached senders.
+    #[inline]
+    pub fn senders(&self) -> u32 {
+        self.senders.count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of attached receivers.
+    #[inline]
+    pub fn receivers(&self) -> u32 {
+        self.receivers.count.load(Ordering::Relaxed)
+    }
+
+    /// Detaches the endpoints of the gone processes, closing the queue once a side has none
+    /// left.
+    ///
+    /// Processes are probed by pid, which may be reused by an unrelated process meanwhile.
+    /// Returns `true` if the queue is closed.
+    #[cfg(feature = "unix")]
+    pub fn probe(&self) -> bool {
+        if !self.is_close() {
+            let senders = self.senders.reap();
+            let receivers = self.receivers.reap();
+            if senders || receivers {
+                self.close();
+            }
+        }
+        self.is_close()
+    }
+
+    /// Probes the peers on behalf of a waiter parked for a while, a no-op without `unix`.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn probe_idle(&self) {
+        #[cfg(feature = "unix")]
+        self.probe();
+    }
+
+    #[inline]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Returns the queue capacity.
+    #[inline]
+    pub fn cap(&self) -> usize {
+        self.cap.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    fn one_lap(&self) -> usize {
+        self.one_lap.load(Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn readable(&self) -> &Notify {
+        &self.readable
+    }
+
+    #[inline]
+    pub fn writable(&self) -> &Notify {
+        &self.writable
+    }
+
+    /// Returns a snapshot of the traffic counters.
+    #[cfg(feature = "stats")]
+    #[inline]
+    pub fn stats(&self) -> QueueStats {
+        self.stats.snapshot()
+    }
+
+    /// Counts `n` pushed items, or a rejection on a full queue if none.
+    #[inline(always)]
+    fn count_push(&self, n: usize) {
+        #[cfg(feature = "stats")]
+        self.stats.push(n, || {
+            let head = self.head.load(Ordering::Relaxed);
+            let tail = self.tail.load(Ordering::Relaxed);
+            spsc::distance(self, head, tail)
+        });
+        #[cfg(not(feature = "stats"))]
+        let _ = n;
+    }
+
+    /// Counts `n` popped items, or an empty poll if none.
+    #[inline(always)]
+    fn count_pop(&self, n: usize) {
+        #[cfg(feature = "stats")]
+        self.stats.pop(n);
+        #[cfg(not(feature = "stats"))]
+        let _ = n;
+    }
+}
+
+#[inline]
+fn slot_at<Q: Queue + ?Sized>(q: &Q, pos: usize) -> &Slot<Q::Item> {
+    let index = pos & (q.header().one_lap() - 1);
+    debug_assert!(index < q.buf().len());
+    unsafe { q.buf().get_unchecked(index) }
 }
 
 pub trait Queue {
@@ -86,68 +494,251 @@ pub trait Queue {
     fn buf(&self) -> &[Slot<Self::Item>];
 }
 
+/// Pushes as [`QueueOps::push_batch`], also telling whether `iter` ran out, rather than the
+/// queue getting full.
+fn push_batch_until<Q, I>(q: &Q, iter: &mut I) -> (usize, bool)
+where
+    Q: QueueOps + ?Sized,
+    I: Iterator<Item = Q::Item>,
+{
+    let header = q.header();
+    let _pass = header.enter(Side::Tx);
+    if header.mode == Mode::Spsc {
+        let (pushed, exhausted) = spsc::push_batch(q, iter);
+        if pushed != 0 || !exhausted {
+            header.count_push(pushed);
+        }
+        return (pushed, exhausted);
+    }
+    let mut tail = header.tail.load(Ordering::Relaxed);
+    let buf = q.buf();
+    let mut pushed = 0;
+
+    let backoff = Backoff::new();
+
+    loop {
+        let (lower, upper) = iter.size_hint();
+        if upper == Some(0) {
+            if pushed != 0 {
+                header.count_push(pushed);
+            }
+            return (pushed, true);
+        }
+        // Without a lower bound, a single slot is claimed ahead of the element.
+        let remain = lower.max(1);
+
+        // Deconstruct the tail.
+        let index = tail & (header.one_lap() - 1);
+        let lap = tail & !(header.one_lap() - 1);
+
+        // Count the slots ready to be written at the tail, the run stops at the end of lap.
+        let max = remain.min(q.capacity() - index);
+        let mut run = 0;
+        while run < max {
+            debug_assert!(index + run < buf.len());
+            let slot = unsafe { buf.get_unchecked(index + run) };
+            if slot.stamp.load(Ordering::Acquire) != tail + run {
+                break;
+            }
+            run += 1;
+        }
+
+        if run == 0 {
+            let slot = unsafe { buf.get_unchecked(index) };
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            if stamp.wrapping_add(header.one_lap()) == tail + 1 {
+                // The queue is full, or the receiver claiming the slot has yet to read it.
+                header.count_push(pushed);
+                return (pushed, false);
+            }
+            // Snooze because we need to wait for the stamp to get updated.
+            backoff.snooze();
+            tail = header.tail.load(Ordering::Relaxed);
+            continue;
+        }
+
+        let new_tail = if index + run < q.capacity() {
+            // Same lap, index moved past the run.
+            tail + run
+        } else {
+            // One lap forward, index wraps around to zero.
+            lap.wrapping_add(header.one_lap())
+        };
+
+        // Try moving the tail past the whole run.
+        match header
+            .tail
+            .compare_exchange_weak(tail, new_tail, Ordering::SeqCst, Ordering::Relaxed)
+        {
+            Ok(_) => {
+                let mut filled = 0;
+                while filled < run {
+                    let Some(value) = iter.next() else { break };
+                    let slot = unsafe { buf.get_unchecked(index + filled) };
+                    unsafe {
+                        slot.value.get().write(MaybeUninit::new(value));
+                    }
+                    slot.stamp.store(tail + filled + 1, Ordering::Release);
+                    filled += 1;
+                }
+                pushed += filled;
+                backoff.reset();
+
+                if filled < run {
+                    // `iter` ran out. Other producers may have claimed the following slots
+                    // meanwhile, so the unused ones are released rather than given back.
+                    for i in filled..run {
+                        let slot = unsafe { buf.get_unchecked(index + i) };
+                        slot.abandoned.store(true, Ordering::Relaxed);
+                        slot.stamp.store(tail + i + 1, Ordering::Release);
+                    }
+                    header.readable.notify();
+                    if pushed != 0 {
+                        header.count_push(pushed);
+                    }
+                    return (pushed, true);
+                }
+                tail = new_tail;
+            }
+            Err(t) => {
+                tail = t;
+                backoff.spin();
+            }
+        }
+    }
+}
+
+/// Pushes as [`QueueOps::push`], leaving the stats to the caller.
+fn push_uncounted<Q: QueueOps + ?Sized>(q: &Q, value: Q::Item) -> Result<(), Q::Item> {
+    let header = q.header();
+    let _pass = header.enter(Side::Tx);
+    if header.mode == Mode::Spsc {
+        return spsc::push(q, value);
+    }
+    match q.claim_tail() {
+        Some(pos) => {
+            // Write the value into the slot and update the stamp.
+            let slot = slot_at(q, pos);
+            unsafe {
+                slot.value.get().write(MaybeUninit::new(value));
+            }
+            slot.stamp.store(pos + 1, Ordering::Release);
+            Ok(())
+        }
+        None => Err(value),
+    }
+}
+
+/// Pops as [`QueueOps::pop`], leaving the stats to the caller.
+fn pop_uncounted<Q: QueueOps + ?Sized>(q: &Q) -> Option<Q::Item> {
+    let header = q.header();
+    let _pass = header.enter(Side::Rx);
+    if header.mode == Mode::Spsc {
+        return spsc::pop(q);
+    }
+    q.claim_head().map(|pos| {
+        // Read the value from the slot and update the stamp.
+        let slot = slot_at(q, pos);
+        let msg = unsafe { slot.value.get().read().assume_init() };
+        slot.stamp
+            .store(pos.wrapping_add(header.one_lap()), Ordering::Release);
+        msg
+    })
+}
+
 pub trait QueueOps: Queue {
     /// Attempts to push an element into the queue.
     fn push(&self, value: Self::Item) -> Result<(), Self::Item> {
-        let header = self.header();
-        self.push_or_else(value, |v, tail, _, _| {
-            let head = header.head.load(Ordering::Relaxed);
-
-            // If the head lags one lap behind the tail as well...
-            if head.wrapping_add(header.one_lap) == tail {
-                // ...then the queue is full.
-                Err(v)
-            } else {
-                Ok(v)
-            }
-        })
+        let res = push_uncounted(self, value);
+        self.header().count_push(res.is_ok() as usize);
+        res
     }
 
     /// Pushes an element into the queue, replacing the oldest element if necessary.
+    ///
+    /// In SPSC mode the producer never moves the head, the element is handed back if the queue
+    /// is full. In MPMC mode an oldest element still held by a [`ReadGuard`] is waited for.
     fn force_push(&self, value: Self::Item) -> Option<Self::Item> {
-        self.push_or_else(value, |v, tail, new_tail, slot| {
-            let header = self.header();
-            let head = tail.wrapping_sub(header.one_lap);
-            let new_head = new_tail.wrapping_sub(header.one_lap);
+        let header = self.header();
+        let _pass = header.enter(Side::Tx);
+        if header.mode == Mode::Spsc {
+            let res = spsc::push(self, value);
+            header.count_push(res.is_ok() as usize);
+            return res.err();
+        }
+        let mut value = Some(value);
+        let claim = self.claim_tail_with(|tail, new_tail, slot| {
+            let head = tail.wrapping_sub(header.one_lap());
+            let new_head = new_tail.wrapping_sub(header.one_lap());
 
             // Try moving the head.
             if header
                 .head
                 .compare_exchange_weak(head, new_head, Ordering::SeqCst, Ordering::Relaxed)
-                .is_ok()
+                .is_err()
             {
-                // Move the tail.
-                header.tail.store(new_tail, Ordering::SeqCst);
+                return ControlFlow::Continue(());
+            }
+            if slot.abandoned.load(Ordering::Relaxed) {
+                // Nothing to replace, free the slot and retry.
+                slot.abandoned.store(false, Ordering::Relaxed);
+                slot.stamp.store(tail, Ordering::Release);
+                return ControlFlow::Continue(());
+            }
 
-                // Swap the previous value.
-                let old = unsafe { slot.value.get().replace(MaybeUninit::new(v)).assume_init() };
+            // Move the tail.
+            header.tail.store(new_tail, Ordering::SeqCst);
 
-                // Update the stamp.
-                slot.stamp.store(tail + 1, Ordering::Release);
+            // Swap the previous value.
+            let v = value.take().expect("the value is pushed once");
+            let old = unsafe { slot.value.get().replace(MaybeUninit::new(v)).assume_init() };
 
-                Err(old)
-            } else {
-                Ok(v)
+            // Update the stamp.
+            slot.stamp.store(tail + 1, Ordering::Release);
+
+            ControlFlow::Break(old)
+        });
+        let old = match claim {
+            Ok(pos) => {
+                let slot = slot_at(self, pos);
+                let v = value.take().expect("the value is pushed once");
+                unsafe {
+                    slot.value.get().write(MaybeUninit::new(v));
+                }
+                slot.stamp.store(pos + 1, Ordering::Release);
+                None
             }
-        })
-        .err()
+            Err(old) => Some(old),
+        };
+        header.count_push(1);
+        old
+    }
+
+    /// Claims the slot at the tail without writing it, returning its position.
+    ///
+    /// Gives up once the queue is full, or the slot at the tail is still held by the receiver
+    /// that claimed it, e.g. through a [`ReadGuard`]: a pending read is never waited for.
+    fn claim_tail(&self) -> Option<usize> {
+        self.claim_tail_with(|_, _, _| ControlFlow::Break(())).ok()
     }
 
-    fn push_or_else<F>(&self, value: Self::Item, f: F) -> Result<(), Self::Item>
+    /// Claims the slot at the tail as [`QueueOps::claim_tail`], but lets `full` decide, given
+    /// the tail, the next tail and the slot, whether to give up while the slot still holds an
+    /// item of the previous lap.
+    fn claim_tail_with<R, F>(&self, mut full: F) -> Result<usize, R>
     where
-        F: Fn(Self::Item, usize, usize, &Slot<Self::Item>) -> Result<Self::Item, Self::Item>,
+        F: FnMut(usize, usize, &Slot<Self::Item>) -> ControlFlow<R>,
     {
         let header = self.header();
         let mut tail = header.tail.load(Ordering::Relaxed);
         let buf = self.buf();
-        let mut value = value;
 
         let backoff = Backoff::new();
 
         loop {
             // Deconstruct the tail.
-            let index = tail & (header.one_lap - 1);
-            let lap = tail & !(header.one_lap - 1);
+            let index = tail & (header.one_lap() - 1);
+            let lap = tail & !(header.one_lap() - 1);
 
             let new_tail = if index + 1 < self.capacity() {
                 // Same lap, incremented index.
@@ -156,7 +747,7 @@ pub trait QueueOps: Queue {
             } else {
                 // One lap forward, index wraps around to zero.
                 // Set to `{ lap: lap.wrapping_add(1), index: 0 }`.
-                lap.wrapping_add(header.one_lap)
+                lap.wrapping_add(header.one_lap())
             };
 
             // Inspect the corresponding slot.
@@ -164,7 +755,7 @@ pub trait QueueOps: Queue {
             let slot = unsafe { buf.get_unchecked(index) };
             let stamp = slot.stamp.load(Ordering::Acquire);
 
-            // If the tail and the stamp match, we may attempt to push.
+            // If the tail and the stamp match, we may attempt to claim.
             if tail == stamp {
                 // Try moving the tail.
                 match header.tail.compare_exchange_weak(
@@ -173,22 +764,18 @@ pub trait QueueOps: Queue {
                     Ordering::SeqCst,
                     Ordering::Relaxed,
                 ) {
-                    Ok(_) => {
-                        // Write the value into the slot and update the stamp.
-                        unsafe {
-                            slot.value.get().write(MaybeUninit::new(value));
-                        }
-                        slot.stamp.store(tail + 1, Ordering::Release);
-                        return Ok(());
-                    }
+                    Ok(_) => return Ok(tail),
                     Err(t) => {
                         tail = t;
                         backoff.spin();
                     }
                 }
-            } else if stamp.wrapping_add(header.one_lap) == tail + 1 {
+            } else if stamp.wrapping_add(header.one_lap()) == tail + 1 {
+                // The queue is full, or the receiver claiming the slot has yet to read it.
                 atomic::fence(Ordering::SeqCst);
-                value = f(value, tail, new_tail, slot)?;
+                if let ControlFlow::Break(r) = full(tail, new_tail, slot) {
+                    return Err(r);
+                }
                 backoff.spin();
                 tail = header.tail.load(Ordering::Relaxed);
             } else {
@@ -201,6 +788,17 @@ pub trait QueueOps: Queue {
 
     /// Attempts to pop an element from the queue.
     fn pop(&self) -> Option<Self::Item> {
+        let res = pop_uncounted(self);
+        self.header().count_pop(res.is_some() as usize);
+        res
+    }
+
+    /// Claims the filled slot at the head without reading it, returning its position.
+    ///
+    /// Released reservations on the way are freed and skipped. Gives up once the queue is empty,
+    /// or the slot at the head is still held by the sender that claimed it, e.g. through a
+    /// [`SlotGuard`]: a pending write is never waited for.
+    fn claim_head(&self) -> Option<usize> {
         let header = self.header();
         let mut head = header.head.load(Ordering::Relaxed);
         let buf = self.buf();
@@ -209,15 +807,15 @@ pub trait QueueOps: Queue {
 
         loop {
             // Deconstruct the head.
-            let index = head & (header.one_lap - 1);
-            let lap = head & !(header.one_lap - 1);
+            let index = head & (header.one_lap() - 1);
+            let lap = head & !(header.one_lap() - 1);
 
             // Inspect the corresponding slot.
             debug_assert!(index < buf.len());
             let slot = unsafe { buf.get_unchecked(index) };
             let stamp = slot.stamp.load(Ordering::Acquire);
 
-            // If the stamp is ahead of the head by 1, we may attempt to pop.
+            // If the stamp is ahead of the head by 1, we may attempt to claim.
             if head + 1 == stamp {
                 let new = if index + 1 < self.capacity() {
                     // Same lap, incremented index.
@@ -226,7 +824,7 @@ pub trait QueueOps: Queue {
                 } else {
                     // One lap forward, index wraps around to zero.
                     // Set to `{ lap: lap.wrapping_add(1), index: 0 }`.
-                    lap.wrapping_add(header.one_lap)
+                    lap.wrapping_add(header.one_lap())
                 };
 
                 // Try moving the head.
@@ -236,41 +834,150 @@ pub trait QueueOps: Queue {
                     Ordering::SeqCst,
                     Ordering::Relaxed,
                 ) {
-                    Ok(_) => {
-                        // Read the value from the slot and update the stamp.
-                        let msg = unsafe { slot.value.get().read().assume_init() };
+                    Ok(_) if slot.abandoned.load(Ordering::Relaxed) => {
+                        // A released reservation, free the slot and move on.
+                        slot.abandoned.store(false, Ordering::Relaxed);
                         slot.stamp
-                            .store(head.wrapping_add(header.one_lap), Ordering::Release);
-                        return Some(msg);
+                            .store(head.wrapping_add(header.one_lap()), Ordering::Release);
+                        head = new;
+                        backoff.reset();
                     }
+                    Ok(_) => return Some(head),
                     Err(h) => {
                         head = h;
                         backoff.spin();
                     }
                 }
             } else if stamp == head {
-                atomic::fence(Ordering::SeqCst);
-                let tail = header.tail.load(Ordering::Relaxed);
+                // The queue is empty, or the sender claiming the slot has yet to write it.
+                return None;
+            } else {
+                // Snooze because we need to wait for the stamp to get updated.
+                backoff.snooze();
+                head = header.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Attempts to push the elements of `iter` into the queue.
+    ///
+    /// Each contiguous run of free slots up to the end of a lap is claimed with a single tail
+    /// update. A run never exceeds the lower bound of [`Iterator::size_hint`], or a single slot
+    /// without one, and an element is taken from `iter` only once its slot is claimed, so the
+    /// remaining ones are left in `iter` if the queue gets full.
+    ///
+    /// The slots claimed for the elements `iter` failed to yield, breaking its lower bound or
+    /// ending without an upper bound of zero, are released as a dropped [`SlotGuard`] does.
+    ///
+    /// Returns the number of pushed elements.
+    fn push_batch<I>(&self, iter: &mut I) -> usize
+    where
+        I: Iterator<Item = Self::Item>,
+    {
+        push_batch_until(self, iter).0
+    }
+
+    /// Attempts to pop at most `max` elements from the queue into `out`.
+    ///
+    /// Each contiguous run of filled slots up to the end of a lap is claimed with a single head
+    /// update.
+    ///
+    /// Returns the number of popped elements.
+    fn pop_batch(&self, out: &mut Vec<Self::Item>, max: usize) -> usize {
+        let header = self.header();
+        let _pass = header.enter(Side::Rx);
+        if header.mode == Mode::Spsc {
+            let popped = spsc::pop_batch(self, out, max);
+            if max != 0 {
+                header.count_pop(popped);
+            }
+            return popped;
+        }
+        let mut head = header.head.load(Ordering::Relaxed);
+        let buf = self.buf();
+        let mut popped = 0;
+
+        let backoff = Backoff::new();
 
-                // If the tail equals the head, that means the channel is empty.
-                if tail == head {
-                    return None;
+        while popped < max {
+            // Deconstruct the head.
+            let index = head & (header.one_lap() - 1);
+            let lap = head & !(header.one_lap() - 1);
+
+            // Count the slots ready to be read at the head, the run stops at the end of lap.
+            let lim = (max - popped).min(self.capacity() - index);
+            let mut run = 0;
+            while run < lim {
+                debug_assert!(index + run < buf.len());
+                let slot = unsafe { buf.get_unchecked(index + run) };
+                if slot.stamp.load(Ordering::Acquire) != head + run + 1 {
+                    break;
                 }
+                run += 1;
+            }
 
-                backoff.spin();
-                head = header.head.load(Ordering::Relaxed);
-            } else {
+            if run == 0 {
+                let slot = unsafe { buf.get_unchecked(index) };
+                let stamp = slot.stamp.load(Ordering::Acquire);
+                if stamp == head {
+                    // The queue is empty, or the sender claiming the slot has yet to write it.
+                    break;
+                }
                 // Snooze because we need to wait for the stamp to get updated.
                 backoff.snooze();
                 head = header.head.load(Ordering::Relaxed);
+                continue;
+            }
+
+            let new_head = if index + run < self.capacity() {
+                // Same lap, index moved past the run.
+                head + run
+            } else {
+                // One lap forward, index wraps around to zero.
+                lap.wrapping_add(header.one_lap())
+            };
+
+            // Reserve ahead, nothing may unwind while the run is claimed.
+            out.reserve(run);
+
+            // Try moving the head past the whole run.
+            match header.head.compare_exchange_weak(
+                head,
+                new_head,
+                Ordering::SeqCst,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    for i in 0..run {
+                        let slot = unsafe { buf.get_unchecked(index + i) };
+                        let msg = slot.take();
+                        slot.stamp
+                            .store((head + i).wrapping_add(header.one_lap()), Ordering::Release);
+                        if let Some(msg) = msg {
+                            out.push(msg);
+                            popped += 1;
+                        }
+                    }
+                    head = new_head;
+                    backoff.reset();
+                }
+                Err(h) => {
+                    head = h;
+                    backoff.spin();
+                }
             }
         }
+
+        if max != 0 {
+            header.count_pop(popped);
+        }
+        popped
     }
 
     /// Returns the capacity of the queue.
     #[inline]
     fn capacity(&self) -> usize {
-        self.buf().len()
+        self.header().cap()
     }
 
     /// Returns `true` if the queue is empty.
@@ -297,7 +1004,7 @@ pub trait QueueOps: Queue {
         //
         // Note: If the tail changes just before we load the head, that means there was a moment
         // when the queue was not full, so it is safe to just return `false`.
-        head.wrapping_add(header.one_lap) == tail
+        head.wrapping_add(header.one_lap()) == tail
     }
 
     /// Returns the number of elements in the queue.
@@ -310,8 +1017,8 @@ pub trait QueueOps: Queue {
 
             // If the tail didn't change, we've got consistent values to work with.
             if header.tail.load(Ordering::SeqCst) == tail {
-                let hix = head & (header.one_lap - 1);
-                let tix = tail & (header.one_lap - 1);
+                let hix = head & (header.one_lap() - 1);
+                let tix = tail & (header.one_lap() - 1);
 
                 return if hix < tix {
                     tix - hix
@@ -338,32 +1045,34 @@ trait QueueDrop: Queue {
             let head = header.head.load(Ordering::Relaxed);
             let tail = header.tail.load(Ordering::Relaxed);
 
-            let hix = head & (header.one_lap - 1);
-            let tix = tail & (header.one_lap - 1);
+            let hix = head & (header.one_lap() - 1);
+            let tix = tail & (header.one_lap() - 1);
 
             let len = if hix < tix {
                 tix - hix
             } else if hix > tix {
-                header.cap - hix + tix
+                header.cap() - hix + tix
             } else if tail == head {
                 0
             } else {
-                header.cap
+                header.cap()
             };
 
             // Loop over all slots that hold a message and drop them.
             for i in 0..len {
                 // Compute the index of the next slot holding a message.
-                let index = if hix + i < header.cap {
+                let index = if hix + i < header.cap() {
                     hix + i
                 } else {
-                    hix + i - header.cap
+                    hix + i - header.cap()
                 };
 
                 unsafe {
                     debug_assert!(index < buf.len());
                     let slot = buf.get_unchecked(index);
-                    (*slot.value.get()).assume_init_drop();
+                    if !slot.abandoned.load(Ordering::Relaxed) {
+                        (*slot.value.get()).assume_init_drop();
+                    }
                 }
             }
         }
@@ -388,14 +1097,35 @@ impl<T: Queue> QueueClose for T {}
 
 trait Endpoint: Sized + Queue {
     #[inline(always)]
-    fn sender(self) -> QueueTx<Self> {
-        QueueTx { tx: self }
+    fn sender(self) -> Result<QueueTx<Self>, AttachError> {
+        self.header().attach_sender()?;
+        Ok(QueueTx { tx: self })
     }
 
     #[inline(always)]
-    fn receiver(self) -> QueueRx<Self> {
-        QueueRx { rx: self }
+    fn receiver(self) -> Result<QueueRx<Self>, AttachError> {
+        self.header().attach_receiver()?;
+        Ok(QueueRx { rx: self })
+    }
+}
+
+/// Attaches a sender to `tx` and a receiver to `rx`, or neither of them.
+fn attach_pair<T: Endpoint, R: Endpoint>(
+    tx: T,
+    rx: R,
+) -> Result<(QueueTx<T>, QueueRx<R>), AttachError> {
+    let (th, rh) = (tx.header(), rx.header());
+    if !th.senders.attach(th.mode == Mode::Spsc) {
+        return Err(AttachError);
+    }
+    if !rh.receivers.attach(rh.mode == Mode::Spsc) {
+        // The sender was never used nor reopened the queue, so it is detached as is.
+        th.senders.detach();
+        return Err(AttachError);
     }
+    th.rejoin();
+    rh.rejoin();
+    Ok((QueueTx { tx }, QueueRx { rx }))
 }
 
 pub trait Sender {
@@ -409,6 +1139,14 @@ pub trait AsyncSender: Sender {
     type Error;
 
     fn send(&self, item: Self::Item) -> impl Future<Output = Result<(), Self::Error>>;
+
+    /// Sends `item` like [`AsyncSender::send`], handing it to `unsent` rather than dropping it
+    /// if the future is dropped before the item is sent.
+    fn send_or_else(
+        &self,
+        item: Self::Item,
+        unsent: impl FnOnce(Self::Item),
+    ) -> impl Future<Output = Result<(), Self::Error>>;
 }
 
 pub trait Receiver {
@@ -416,6 +1154,24 @@ pub trait Receiver {
     type TryError;
 
     fn try_recv(&self) -> Result<Self::Item, Self::TryError>;
+
+    /// Receives at most `max` items into `buf`, failing only if none is received.
+    fn try_recv_many(
+        &self,
+        buf: &mut Vec<Self::Item>,
+        max: usize,
+    ) -> Result<usize, Self::TryError> {
+        let mut n = 0;
+        while n < max {
+            match self.try_recv() {
+                Ok(item) => buf.push(item),
+                Err(e) if n == 0 => return Err(e),
+                Err(_) => break,
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
 }
 
 pub trait AsyncReceiver: Receiver {
@@ -439,6 +1195,20 @@ pub trait QueueChannel {
         self.handle().is_close()
     }
 
+    /// Closes the queue if the peer process is gone, see [`Header::probe`].
+    #[cfg(feature = "unix")]
+    #[inline(always)]
+    fn probe(&self) -> bool {
+        self.handle().header().probe()
+    }
+
+    /// Returns a snapshot of the traffic counters, see [`Header::stats`].
+    #[cfg(feature = "stats")]
+    #[inline(always)]
+    fn stats(&self) -> QueueStats {
+        self.handle().header().stats()
+    }
+
     #[inline(always)]
     fn capacity(&self) -> usize {
         self.handle().capacity()
@@ -468,10 +1238,44 @@ pub enum TrySendError<T> {
 
 #[derive(Debug)]
 pub enum TryRecvError {
+    /// The queue is empty, or the slot at its head is still reserved by a sender, e.g. through
+    /// a [`SlotGuard`], holding back the items sent behind it until committed or released.
     Empty,
     Disconnected,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Disconnected(T),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    Timeout,
+    Disconnected,
+}
+
+/// The queue is disconnected, the unsent item is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// The queue is disconnected and drained.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+/// The queue is in SPSC mode and the side has an endpoint attached already, in any process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachError;
+
+impl core::fmt::Display for AttachError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "SPSC queue side already has an endpoint")
+    }
+}
+
+impl core::error::Error for AttachError {}
+
 #[derive(Debug, PartialEq)]
 #[repr(transparent)]
 pub struct QueueTx<T: Queue> {
@@ -484,14 +1288,27 @@ pub struct QueueRx<T: Queue> {
     rx: T,
 }
 
+/// # Panics
+///
+/// Panics if the queue is in SPSC mode, as this sender is attached already.
 impl<T: Queue + Clone> Clone for QueueTx<T> {
     fn clone(&self) -> Self {
+        self.tx
+            .header()
+            .attach_sender()
+            .expect("SPSC queue already has a sender");
         Self {
             tx: self.tx.clone(),
         }
     }
 }
 
+impl<T: Queue> Drop for QueueTx<T> {
+    fn drop(&mut self) {
+        self.tx.header().detach_sender();
+    }
+}
+
 impl<T: Queue> Sender for QueueTx<T> {
     type Item = T::Item;
 
@@ -518,7 +1335,134 @@ impl<T: Queue> QueueTx<T> {
         if self.tx.header().is_close() {
             return Err(TrySendError::Disconnected);
         }
-        self.tx.push(value).map_err(TrySendError::Full)
+        self.tx.push(value).map_err(TrySendError::Full)?;
+        self.tx.header().readable.notify();
+        Ok(())
+    }
+
+    /// Pushes for a sender waiting on a full queue, counting a rejection on its first attempt
+    /// only, so that the stats see one rejection per wait rather than one per retry.
+    #[cfg(feature = "std")]
+    fn push_waiting(&self, value: T::Item, rejected: &mut bool) -> Result<(), T::Item> {
+        let res = push_uncounted(&self.tx, value);
+        if res.is_ok() || !core::mem::replace(rejected, true) {
+            self.tx.header().count_push(res.is_ok() as usize);
+        }
+        res
+    }
+
+    /// Sends the items of `iter` until the queue is full, see [`QueueOps::push_batch`].
+    ///
+    /// Returns the number of sent items, the unsent ones are left in `iter`. Fails with
+    /// [`TrySendError::Full`] only if the queue is full before any item could be sent.
+    pub fn try_send_many<I>(&self, iter: &mut I) -> Result<usize, TrySendError<()>>
+    where
+        I: Iterator<Item = T::Item>,
+    {
+        if self.tx.header().is_close() {
+            return Err(TrySendError::Disconnected);
+        }
+        match push_batch_until(&self.tx, iter) {
+            (0, false) => Err(TrySendError::Full(())),
+            (n, _) => {
+                self.tx.header().readable.notify();
+                Ok(n)
+            }
+        }
+    }
+
+    /// Reserves the slot at the tail, so that the item is constructed in place in the queue.
+    ///
+    /// Nothing is sent until [`SlotGuard::commit`], the slot is released if the guard drops.
+    /// Receivers reaching the slot find the queue empty meanwhile, even with items sent behind
+    /// it, and are notified once it is committed or released. The guard should be short-lived.
+    pub fn reserve(&mut self) -> Result<SlotGuard<'_, T>, TrySendError<()>> {
+        if self.tx.header().is_close() {
+            return Err(TrySendError::Disconnected);
+        }
+        SlotGuard::reserve(&self.tx).ok_or(TrySendError::Full(()))
+    }
+
+    /// Sends an item, parking while the queue is full.
+    ///
+    /// The receiver of the other process wakes the sender through the futex word in the shared
+    /// [`Header`] once it pops.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn send(&self, value: T::Item) -> SendFuture<'_, T> {
+        self.send_or_else(value, drop)
+    }
+
+    /// Sends an item as [`QueueTx::send`], handing it to `unsent` if the future is dropped
+    /// before the item is sent.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn send_or_else<F: FnOnce(T::Item)>(
+        &self,
+        value: T::Item,
+        unsent: F,
+    ) -> SendFuture<'_, T, F> {
+        SendFuture {
+            tx: self,
+            item: Some(value),
+            unsent: Some(unsent),
+            rejected: false,
+            park: notify::Park::new(),
+        }
+    }
+
+    /// Sends an item, blocking the thread while the queue is full.
+    ///
+    /// The thread parks on the futex word in the shared [`Header`] and is woken by the receiver
+    /// of the other process once it pops.
+    #[cfg(feature = "std")]
+    pub fn send_blocking(&self, value: T::Item) -> Result<(), SendError<T::Item>> {
+        self.send_deadline(value, None).map_err(|e| match e {
+            SendTimeoutError::Disconnected(value) => SendError(value),
+            SendTimeoutError::Timeout(_) => unreachable!("no deadline"),
+        })
+    }
+
+    /// Sends an item as [`QueueTx::send_blocking`], giving up once `timeout` elapses.
+    #[cfg(feature = "std")]
+    pub fn send_timeout(
+        &self,
+        value: T::Item,
+        timeout: core::time::Duration,
+    ) -> Result<(), SendTimeoutError<T::Item>> {
+        self.send_deadline(value, std::time::Instant::now().checked_add(timeout))
+    }
+
+    #[cfg(feature = "std")]
+    fn send_deadline(
+        &self,
+        value: T::Item,
+        deadline: Option<std::time::Instant>,
+    ) -> Result<(), SendTimeoutError<T::Item>> {
+        let header = self.tx.header();
+        let mut item = Some(value);
+        let mut rejected = false;
+        let res = header.writable().block_on(
+            deadline,
+            || header.probe_idle(),
+            || {
+                let value = item.take().expect("item is put back on retry");
+                if header.is_close() {
+                    return Some(Err(SendTimeoutError::Disconnected(value)));
+                }
+                match self.push_waiting(value, &mut rejected) {
+                    Ok(()) => {
+                        header.readable().notify();
+                        Some(Ok(()))
+                    }
+                    Err(value) => {
+                        item = Some(value);
+                        None
+                    }
+                }
+            },
+        );
+        res.unwrap_or_else(|| Err(SendTimeoutError::Timeout(item.take().unwrap())))
     }
 
     //     #[inline(always)]
@@ -542,14 +1486,27 @@ impl<T: Queue> QueueTx<T> {
     //     }
 }
 
+/// # Panics
+///
+/// Panics if the queue is in SPSC mode, as this receiver is attached already.
 impl<T: Queue + Clone> Clone for QueueRx<T> {
     fn clone(&self) -> Self {
+        self.rx
+            .header()
+            .attach_receiver()
+            .expect("SPSC queue already has a receiver");
         Self {
             rx: self.rx.clone(),
         }
     }
 }
 
+impl<T: Queue> Drop for QueueRx<T> {
+    fn drop(&mut self) {
+        self.rx.header().detach_receiver();
+    }
+}
+
 impl<T: Queue> Receiver for QueueRx<T> {
     type Item = T::Item;
 
@@ -559,6 +1516,15 @@ impl<T: Queue> Receiver for QueueRx<T> {
     fn try_recv(&self) -> Result<Self::Item, Self::TryError> {
         self.try_recv()
     }
+
+    #[inline(always)]
+    fn try_recv_many(
+        &self,
+        buf: &mut Vec<Self::Item>,
+        max: usize,
+    ) -> Result<usize, Self::TryError> {
+        self.try_recv_many(buf, max)
+    }
 }
 
 impl<T: Queue> QueueChannel for QueueRx<T> {
@@ -573,15 +1539,112 @@ impl<T: Queue> QueueChannel for QueueRx<T> {
 impl<T: Queue> QueueRx<T> {
     #[inline(always)]
     pub fn try_recv(&self) -> Result<T::Item, TryRecvError> {
-        self.rx.pop().ok_or_else(|| {
-            if self.is_close() {
-                TryRecvError::Disconnected
-            } else {
-                TryRecvError::Empty
+        match self.rx.pop() {
+            Some(item) => {
+                self.rx.header().writable.notify();
+                Ok(item)
             }
+            None if self.is_close() => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives for a receiver waiting on an empty queue, counting an empty poll on its first
+    /// attempt only, so that the stats see one empty poll per wait rather than one per retry.
+    #[cfg(feature = "std")]
+    fn recv_waiting(&self, polled: &mut bool) -> Result<T::Item, TryRecvError> {
+        let res = pop_uncounted(&self.rx);
+        if res.is_some() || !core::mem::replace(polled, true) {
+            self.rx.header().count_pop(res.is_some() as usize);
+        }
+        match res {
+            Some(item) => {
+                self.rx.header().writable.notify();
+                Ok(item)
+            }
+            None if self.is_close() => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives at most `max` items into `buf`, see [`QueueOps::pop_batch`].
+    ///
+    /// Fails only if no item is received.
+    pub fn try_recv_many(&self, buf: &mut Vec<T::Item>, max: usize) -> Result<usize, TryRecvError> {
+        match self.rx.pop_batch(buf, max) {
+            0 if max == 0 => Ok(0),
+            0 if self.is_close() => Err(TryRecvError::Disconnected),
+            0 => Err(TryRecvError::Empty),
+            n => {
+                self.rx.header().writable.notify();
+                Ok(n)
+            }
+        }
+    }
+
+    /// Receives the item at the head in place.
+    ///
+    /// The item is dropped and its slot acknowledged once the guard drops. Senders reaching the
+    /// slot find the queue full meanwhile, the guard should be short-lived.
+    pub fn peek_front(&mut self) -> Result<ReadGuard<'_, T>, TryRecvError> {
+        match ReadGuard::front(&self.rx) {
+            Some(guard) => Ok(guard),
+            None if self.is_close() => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives an item, parking while the queue is empty.
+    ///
+    /// The sender of the other process wakes the receiver through the futex word in the shared
+    /// [`Header`] once it pushes.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn recv(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            rx: self,
+            polled: false,
+            park: notify::Park::new(),
+        }
+    }
+
+    /// Receives an item, blocking the thread while the queue is empty.
+    ///
+    /// The thread parks on the futex word in the shared [`Header`] and is woken by the sender of
+    /// the other process once it pushes.
+    #[cfg(feature = "std")]
+    pub fn recv_blocking(&self) -> Result<T::Item, RecvError> {
+        self.recv_deadline(None).map_err(|e| match e {
+            RecvTimeoutError::Disconnected => RecvError,
+            RecvTimeoutError::Timeout => unreachable!("no deadline"),
         })
     }
 
+    /// Receives an item as [`QueueRx::recv_blocking`], giving up once `timeout` elapses.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&self, timeout: core::time::Duration) -> Result<T::Item, RecvTimeoutError> {
+        self.recv_deadline(std::time::Instant::now().checked_add(timeout))
+    }
+
+    #[cfg(feature = "std")]
+    fn recv_deadline(
+        &self,
+        deadline: Option<std::time::Instant>,
+    ) -> Result<T::Item, RecvTimeoutError> {
+        let header = self.rx.header();
+        let mut polled = false;
+        let res = header.readable().block_on(
+            deadline,
+            || header.probe_idle(),
+            || match self.recv_waiting(&mut polled) {
+                Ok(item) => Some(Ok(item)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err(RecvTimeoutError::Disconnected)),
+            },
+        );
+        res.unwrap_or(Err(RecvTimeoutError::Timeout))
+    }
+
     // #[inline(always)]
     // pub fn capacity(&self) -> usize {
     //     self.rx.capacity()
@@ -602,3 +1665,127 @@ impl<T: Queue> QueueRx<T> {
     //     self.rx.len()
     // }
 }
+
+#[cfg(feature = "std")]
+impl<T: Queue> AsyncSender for QueueTx<T> {
+    type Error = SendError<T::Item>;
+
+    #[inline(always)]
+    fn send(&self, item: Self::Item) -> impl Future<Output = Result<(), Self::Error>> {
+        self.send(item)
+    }
+
+    #[inline(always)]
+    fn send_or_else(
+        &self,
+        item: Self::Item,
+        unsent: impl FnOnce(Self::Item),
+    ) -> impl Future<Output = Result<(), Self::Error>> {
+        self.send_or_else(item, unsent)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Queue> AsyncReceiver for QueueRx<T> {
+    type Error = RecvError;
+
+    #[inline(always)]
+    fn recv(&self) -> impl Future<Output = Result<Self::Item, Self::Error>> {
+        self.recv()
+    }
+}
+
+/// Future returned by [`QueueTx::send`] and [`QueueTx::send_or_else`].
+#[cfg(feature = "std")]
+pub struct SendFuture<'a, T: Queue, F: FnOnce(T::Item) = fn(<T as Queue>::Item)> {
+    tx: &'a QueueTx<T>,
+    item: Option<T::Item>,
+    unsent: Option<F>,
+    rejected: bool,
+    park: notify::Park,
+}
+
+#[cfg(feature = "std")]
+impl<T: Queue, F: FnOnce(T::Item)> Unpin for SendFuture<'_, T, F> {}
+
+#[cfg(feature = "std")]
+impl<T: Queue, F: FnOnce(T::Item)> Future for SendFuture<'_, T, F> {
+    type Output = Result<(), SendError<T::Item>>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let header = this.tx.tx.header();
+        let tx = this.tx;
+        let item = &mut this.item;
+        let rejected = &mut this.rejected;
+        this.park.poll(
+            header.writable(),
+            cx,
+            || header.probe_idle(),
+            || {
+                let value = item.take().expect("`SendFuture` polled after completion");
+                if header.is_close() {
+                    return Some(Err(SendError(value)));
+                }
+                // Pushed directly, `try_send` would drop the value on a close racing the check.
+                match tx.push_waiting(value, rejected) {
+                    Ok(()) => {
+                        header.readable().notify();
+                        Some(Ok(()))
+                    }
+                    Err(value) => {
+                        *item = Some(value);
+                        None
+                    }
+                }
+            },
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Queue, F: FnOnce(T::Item)> Drop for SendFuture<'_, T, F> {
+    fn drop(&mut self) {
+        self.park.cancel(self.tx.tx.header().writable());
+        if let (Some(item), Some(unsent)) = (self.item.take(), self.unsent.take()) {
+            unsent(item);
+        }
+    }
+}
+
+/// Future returned by [`QueueRx::recv`].
+#[cfg(feature = "std")]
+pub struct RecvFuture<'a, T: Queue> {
+    rx: &'a QueueRx<T>,
+    polled: bool,
+    park: notify::Park,
+}
+
+#[cfg(feature = "std")]
+impl<T: Queue> Future for RecvFuture<'_, T> {
+    type Output = Result<T::Item, RecvError>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let rx = this.rx;
+        let header = rx.rx.header();
+        let polled = &mut this.polled;
+        this.park.poll(
+            header.readable(),
+            cx,
+            || header.probe_idle(),
+            || match rx.recv_waiting(polled) {
+                Ok(item) => Some(Ok(item)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => Some(Err(RecvError)),
+            },
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Queue> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        self.park.cancel(self.rx.rx.header().readable());
+    }
+}
@@ -0,0 +1,500 @@
+//! A broadcast ring in shared memory: one publisher, many subscribers reading every message.
+//!
+//! Each subscriber keeps its own cursor, the publisher never waits for them: a subscriber falling
+//! more than a lap behind misses the overwritten messages and is told so by
+//! [`TryRecvError::Lagged`].
+//!
+//! A published token is wrapped in a shared record counting the subscribers that may still read
+//! it. A subscriber claims one reference from the slot and hands it back with
+//! [`SharedToken::release`], the references of lagged subscribers are dropped by the publisher
+//! when it overwrites the slot, and whoever drops the last one frees the payload.
+
+use core::alloc::Layout;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{self, AtomicBool, AtomicU32, AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use core::task::{Context, Poll};
+
+use crossbeam_utils::CachePadded;
+
+use crate::boxed::PBox;
+use crate::channel::{Notify, SendError};
+use crate::mem::{MemAlloc, MemAllocator, Meta};
+use crate::msg::{Envelope, Message};
+use crate::reg::{AsEntry, EntryGuard, Finalize, Project, Resource};
+use crate::token::{PackToken, Token, TokenOf};
+
+/// Packs a position in the upper half and a count in the lower half.
+#[inline]
+const fn pack(pos: u32, count: u32) -> u64 {
+    ((pos as u64) << 32) | count as u64
+}
+
+#[inline]
+const fn unpack(v: u64) -> (u32, u32) {
+    ((v >> 32) as u32, v as u32)
+}
+
+/// A published message, shared by the subscribers.
+struct Shared<H: Envelope, M: Meta> {
+    rc: AtomicU32,
+    header: H,
+    token: Token<M>,
+}
+
+type SharedOf<H, M> = TokenOf<Shared<H, M>, M>;
+
+/// Drops `n` references of a shared message, freeing it with the last one.
+///
+/// # Safety
+///
+/// The caller owns `n` references of `shared`.
+unsafe fn release<H: Envelope, M: Meta, A: MemAllocator<Meta = M>>(
+    shared: SharedOf<H, M>,
+    n: u32,
+    alloc: &A,
+) {
+    let rc = unsafe { &shared.as_ptr(alloc).as_ref().rc };
+    if rc.fetch_sub(n, Ordering::Release) != n {
+        return;
+    }
+    atomic::fence(Ordering::Acquire);
+
+    let (ptr, meta, alloc) = PBox::into_raw_ptr(shared.boxed(alloc));
+    let Shared { token, .. } = unsafe { ptr.read() };
+    token.discard(alloc);
+    alloc.dealloc(meta, Layout::new::<Shared<H, M>>());
+}
+
+struct Slot<H: Envelope, M: Meta> {
+    /// The sequence of the message, i.e. its position plus one, and the references left for the
+    /// subscribers that have yet to claim it.
+    state: AtomicU64,
+
+    /// The message, valid once the sequence is set with a non-zero count.
+    value: UnsafeCell<MaybeUninit<SharedOf<H, M>>>,
+}
+
+struct Header {
+    /// The next position to publish and the number of subscribers.
+    ///
+    /// Both are packed so that a new subscriber starts at a position the publisher counts it in.
+    tail: CachePadded<AtomicU64>,
+
+    /// The publisher is gone.
+    close: AtomicBool,
+
+    /// A publisher is attached.
+    publisher: AtomicBool,
+
+    /// Subscribers parked on the ring, notified on publish.
+    readable: Notify,
+}
+
+impl Header {
+    const fn new() -> Self {
+        Self {
+            tail: CachePadded::new(AtomicU64::new(0)),
+            close: AtomicBool::new(false),
+            publisher: AtomicBool::new(false),
+            readable: Notify::new(),
+        }
+    }
+
+    fn reset(&self) {
+        self.close.store(false, Ordering::Relaxed);
+        self.publisher.store(false, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct ViewOfBroadcast<H: Envelope, M: Meta> {
+    ptr: NonNull<[Slot<H, M>]>,
+}
+
+unsafe impl<H: Envelope, M: Meta> Send for ViewOfBroadcast<H, M> {}
+
+impl<H: Envelope, M: Meta> Clone for ViewOfBroadcast<H, M> {
+    fn clone(&self) -> Self {
+        Self { ptr: self.ptr }
+    }
+}
+
+pub trait AsTokenBroadcast<H: Envelope, M: Meta>: AsEntry<TokenBroadcast<H, M>> {}
+impl<H: Envelope, M: Meta, T: AsEntry<TokenBroadcast<H, M>>> AsTokenBroadcast<H, M> for T {}
+
+pub type BroadcastView<H, M, E> = EntryGuard<E, TokenBroadcast<H, M>, ViewOfBroadcast<H, M>>;
+
+/// A broadcast ring of tokens, its capacity is rounded up to a power of two.
+pub struct TokenBroadcast<H: Envelope, M: Meta> {
+    header: Header,
+    buf: TokenOf<[Slot<H, M>], M>,
+}
+
+unsafe impl<H: Send + Sync + Envelope, M: Meta> Send for TokenBroadcast<H, M> {}
+unsafe impl<H: Send + Sync + Envelope, M: Meta> Sync for TokenBroadcast<H, M> {}
+
+impl<H: Envelope, M: Meta> UnwindSafe for TokenBroadcast<H, M> {}
+impl<H: Envelope, M: Meta> RefUnwindSafe for TokenBroadcast<H, M> {}
+
+impl<H: Envelope, M: Meta> Finalize for TokenBroadcast<H, M> {
+    fn finalize(&self) {
+        // Let a new publisher attach on next acquisition.
+        self.header.reset()
+    }
+}
+
+impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Resource<A> for TokenBroadcast<H, M> {
+    type Config = usize;
+
+    fn new(conf: Self::Config, ctx: A) -> (Self, A) {
+        let cap = conf.next_power_of_two();
+        assert!(cap <= 1 << 30, "capacity overflows the position space");
+
+        let alloc = ctx;
+        let header = Header::new();
+        let buffer: PBox<_, A> = PBox::new_slice_in(
+            cap,
+            |i| Slot {
+                // The slot held position `i - cap`, one lap before the first pass.
+                state: AtomicU64::new(pack((i as u32).wrapping_sub(cap as u32).wrapping_add(1), 0)),
+                value: UnsafeCell::new(MaybeUninit::uninit()),
+            },
+            alloc,
+        );
+        let (buf, alloc) = buffer.token_of_with();
+        (TokenBroadcast { header, buf }, alloc)
+    }
+
+    fn free(s: Self, ctx: A) -> A {
+        let alloc = ctx;
+        let Self { header: _, buf } = s;
+        let b = buf.boxed(alloc);
+        for slot in b.iter() {
+            // The references left belong to subscribers that are gone.
+            let (_, left) = unpack(slot.state.load(Ordering::Acquire));
+            if left != 0 {
+                unsafe { release(slot.value.get().read().assume_init(), left, b.allocator()) };
+            }
+        }
+        PBox::drop_in(b)
+    }
+}
+
+impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Project<A> for TokenBroadcast<H, M> {
+    type View = ViewOfBroadcast<H, M>;
+
+    #[inline]
+    fn project(&self, ctx: A) -> (Self::View, A) {
+        let alloc = ctx;
+        let buf = self.buf.as_ptr(&alloc);
+        (ViewOfBroadcast { ptr: buf }, alloc)
+    }
+}
+
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> BroadcastView<H, M, E> {
+    #[inline]
+    fn header(&self) -> &Header {
+        &self.as_ref().header
+    }
+
+    #[inline]
+    fn buf(&self) -> &[Slot<H, M>] {
+        // Safety: only view in TokenBroadcast context.
+        unsafe { self.view.ptr.as_ref() }
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.buf().len()
+    }
+
+    #[inline]
+    pub fn subscribers(&self) -> usize {
+        unpack(self.header().tail.load(Ordering::Relaxed)).1 as usize
+    }
+
+    #[inline]
+    pub fn is_close(&self) -> bool {
+        self.header().close.load(Ordering::Acquire)
+    }
+
+    /// Attaches the publisher, `None` if another one is attached.
+    pub fn publisher(self) -> Option<Publisher<H, M, E>> {
+        let header = self.header();
+        if header.publisher.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+        header.close.store(false, Ordering::Release);
+        Some(Publisher { view: self })
+    }
+
+    /// Attaches a subscriber, which receives the messages published from now on.
+    pub fn subscribe(self) -> Subscriber<H, M, E> {
+        let (next, _) = unpack(self.header().tail.fetch_add(1, Ordering::AcqRel));
+        Subscriber { view: self, next }
+    }
+}
+
+/// The sole writer of a [`TokenBroadcast`], the ring is closed when it drops.
+pub struct Publisher<H: Envelope, M: Meta, E: AsTokenBroadcast<H, M>> {
+    view: BroadcastView<H, M, E>,
+}
+
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Drop for Publisher<H, M, E> {
+    fn drop(&mut self) {
+        let header = self.view.header();
+        header.close.store(true, Ordering::SeqCst);
+        header.publisher.store(false, Ordering::Release);
+        header.readable.notify();
+    }
+}
+
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Publisher<H, M, E> {
+    /// Publishes a token to every current subscriber, overwriting the oldest message.
+    ///
+    /// The token is handed back if there is no subscriber to read it.
+    pub fn try_send<A: MemAllocator<Meta = M>>(
+        &mut self,
+        token: PackToken<H, M>,
+        alloc: &A,
+    ) -> Result<(), SendError<PackToken<H, M>>> {
+        let header = self.view.header();
+        let buf = self.view.buf();
+
+        let mut cur = header.tail.load(Ordering::Acquire);
+        let (pos, subs) = unpack(cur);
+        if subs == 0 {
+            return Err(SendError(token));
+        }
+
+        // The publisher holds a reference of its own until the position is committed.
+        let (token, env) = token.unpack();
+        let shared = Shared {
+            rc: AtomicU32::new(subs + 1),
+            header: env,
+            token,
+        };
+        let (shared, _) = PBox::new_in(shared, alloc).token_of_with();
+        let rc = unsafe { &shared.as_ptr(alloc).as_ref().rc };
+
+        let seq = pos.wrapping_add(1);
+        let slot = &buf[pos as usize & (buf.len() - 1)];
+
+        // Retire the message one lap behind, the references left belong to lagged subscribers.
+        let (_, left) = unpack(slot.state.swap(pack(seq, 0), Ordering::AcqRel));
+        if left != 0 {
+            unsafe { release(slot.value.get().read().assume_init(), left, alloc) };
+        }
+        unsafe { slot.value.get().write(MaybeUninit::new(shared)) };
+        slot.state.store(pack(seq, subs), Ordering::SeqCst);
+
+        let mut granted = subs;
+        while let Err(now) = header.tail.compare_exchange_weak(
+            cur,
+            pack(seq, unpack(cur).1),
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            // Subscribers joined at `pos` meanwhile, grant each of them a reference.
+            let (_, subs) = unpack(now);
+            if subs > granted {
+                rc.fetch_add(subs - granted, Ordering::Relaxed);
+                slot.state
+                    .fetch_add((subs - granted) as u64, Ordering::SeqCst);
+                granted = subs;
+            }
+            cur = now;
+        }
+
+        unsafe { release(slot.value.get().read().assume_init(), 1, alloc) };
+        header.readable.notify();
+        Ok(())
+    }
+}
+
+/// A reader of a [`TokenBroadcast`] with its own cursor.
+pub struct Subscriber<H: Envelope, M: Meta, E: AsTokenBroadcast<H, M>> {
+    view: BroadcastView<H, M, E>,
+    next: u32,
+}
+
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Drop for Subscriber<H, M, E> {
+    fn drop(&mut self) {
+        // The unclaimed references are dropped by the publisher as it overwrites them.
+        self.view.header().tail.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Subscriber<H, M, E> {
+    #[inline]
+    pub fn try_recv(&mut self) -> Result<SharedToken<H, M>, TryRecvError> {
+        try_recv_at(self.view.header(), self.view.buf(), &mut self.next)
+    }
+
+    /// Receives the next message, parking while the subscriber is caught up.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn recv(&mut self) -> RecvFuture<'_, H, M, E> {
+        RecvFuture {
+            sub: self,
+            park: super::notify::Park::new(),
+        }
+    }
+
+    /// Attaches another subscriber, which receives the messages published from now on.
+    pub fn resubscribe(&self) -> Self
+    where
+        E: Clone,
+    {
+        self.view.clone().subscribe()
+    }
+
+    #[inline]
+    pub fn is_close(&self) -> bool {
+        self.view.is_close()
+    }
+}
+
+fn try_recv_at<H: Envelope, M: Meta>(
+    header: &Header,
+    buf: &[Slot<H, M>],
+    next: &mut u32,
+) -> Result<SharedToken<H, M>, TryRecvError> {
+    let cap = buf.len() as u32;
+    loop {
+        // Loaded first: the last message is visible once the close is.
+        let close = header.close.load(Ordering::Acquire);
+
+        let slot = &buf[*next as usize & (buf.len() - 1)];
+        let state = slot.state.load(Ordering::SeqCst);
+        let (seq, left) = unpack(state);
+        let ahead = seq.wrapping_sub(next.wrapping_add(1)) as i32;
+
+        if ahead > 0 {
+            // Overwritten, skip to the oldest message still in the ring.
+            let (tail, _) = unpack(header.tail.load(Ordering::Acquire));
+            let mut oldest = tail.wrapping_sub(cap);
+            let floor = seq.wrapping_sub(cap);
+            if floor.wrapping_sub(oldest) as i32 > 0 {
+                oldest = floor;
+            }
+            let missed = oldest.wrapping_sub(*next);
+            *next = oldest;
+            return Err(TryRecvError::Lagged(missed as u64));
+        }
+
+        if ahead < 0 || left == 0 {
+            return Err(if close {
+                TryRecvError::Disconnected
+            } else {
+                TryRecvError::Empty
+            });
+        }
+
+        // Read ahead of the claim like a seqlock, the claim fails if the slot was overwritten.
+        let value = unsafe { ptr::read_volatile(slot.value.get()) };
+        if slot
+            .state
+            .compare_exchange_weak(state, state - 1, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+        {
+            *next = next.wrapping_add(1);
+            return Ok(SharedToken {
+                shared: unsafe { value.assume_init() },
+            });
+        }
+    }
+}
+
+/// A message received from a [`TokenBroadcast`], sharing its payload with the other subscribers.
+///
+/// Like a token it has no destructor: hand it back with [`SharedToken::release`], the last
+/// release frees the payload.
+pub struct SharedToken<H: Envelope, M: Meta> {
+    shared: SharedOf<H, M>,
+}
+
+impl<H: Envelope, M: Meta> SharedToken<H, M> {
+    #[inline]
+    fn shared<A: MemAlloc<Meta = M>>(&self, alloc: &A) -> &Shared<H, M> {
+        unsafe { self.shared.as_ptr(alloc).as_ref() }
+    }
+
+    #[inline]
+    pub fn header<A: MemAlloc<Meta = M>>(&self, alloc: &A) -> &H {
+        &self.shared(alloc).header
+    }
+
+    /// Borrows the payload if it is a `T`.
+    #[inline]
+    pub fn get<T: Message + ?Sized, A: MemAlloc<Meta = M>>(&self, alloc: &A) -> Option<&T> {
+        let ptr = self.shared(alloc).token.as_ptr::<T, A>(alloc)?;
+        Some(unsafe { ptr.as_ref() })
+    }
+
+    /// Hands the reference back, freeing the payload if it is the last one.
+    ///
+    /// The payload is freed without running its destructor.
+    #[inline]
+    pub fn release<A: MemAllocator<Meta = M>>(self, alloc: &A) {
+        unsafe { release(self.shared, 1, alloc) }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    /// The subscriber fell behind and skipped this many messages.
+    Lagged(u64),
+    Disconnected,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvError {
+    /// The subscriber fell behind and skipped this many messages.
+    Lagged(u64),
+    /// The publisher is gone and the subscriber is caught up.
+    Disconnected,
+}
+
+/// Future returned by [`Subscriber::recv`].
+#[cfg(feature = "std")]
+pub struct RecvFuture<'a, H: Envelope, M: Meta, E: AsTokenBroadcast<H, M>> {
+    sub: &'a mut Subscriber<H, M, E>,
+    park: super::notify::Park,
+}
+
+#[cfg(feature = "std")]
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Future for RecvFuture<'_, H, M, E> {
+    type Output = Result<SharedToken<H, M>, RecvError>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let Subscriber { view, next } = &mut *this.sub;
+        let (header, buf) = (view.header(), view.buf());
+        // The publisher is not tracked by pid, there is nothing to probe.
+        this.park.poll(
+            &header.readable,
+            cx,
+            || {},
+            || match try_recv_at(header, buf, next) {
+                Ok(shared) => Some(Ok(shared)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Lagged(n)) => Some(Err(RecvError::Lagged(n))),
+                Err(TryRecvError::Disconnected) => Some(Err(RecvError::Disconnected)),
+            },
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: AsTokenBroadcast<H, M>, H: Envelope, M: Meta> Drop for RecvFuture<'_, H, M, E> {
+    fn drop(&mut self) {
+        self.park.cancel(&self.sub.view.header().readable);
+    }
+}
@@ -1,13 +1,18 @@
+#[cfg(feature = "resize")]
+use alloc::vec::Vec;
 use core::cell::UnsafeCell;
 use core::marker::PhantomData;
-use core::mem::MaybeUninit;
 use core::ops::Deref;
 use core::panic::{RefUnwindSafe, UnwindSafe};
-use core::ptr::{self};
-use core::sync::atomic::AtomicUsize;
+#[cfg(feature = "resize")]
+use core::time::Duration;
+#[cfg(feature = "resize")]
+use std::time::Instant;
 
 use crate::boxed::PBox;
-use crate::channel::{Endpoint, Header, Queue, QueueRx, QueueTx, Slot};
+use crate::channel::{
+    AttachError, Endpoint, Header, Mode, Queue, QueueRx, QueueTx, Slot, attach_pair,
+};
 use crate::mem::{MemAllocator, Meta};
 use crate::msg::Envelope;
 use crate::reg::{AsEntry, EntryGuard, Finalize, Project, Resource};
@@ -16,25 +21,46 @@ use crate::token::{PackToken, TokenOf};
 type Tokens<H, M> = [Slot<PackToken<H, M>>];
 type TokenOfTokens<H, M> = TokenOf<Tokens<H, M>, M>;
 
+/// The slots of a [`TokenQueue`] as seen by this process.
+///
+/// With the `resize` feature the slots are resolved against the allocator base on each access
+/// rather than cached, so that a view follows a resize.
 #[derive(Debug)]
 #[repr(transparent)]
 pub struct ViewOfQueue<H: Envelope, M: Meta> {
-    ptr: ptr::NonNull<Tokens<H, M>>,
+    #[cfg(feature = "resize")]
+    base: *const u8,
+    #[cfg(not(feature = "resize"))]
+    slots: *const Tokens<H, M>,
+    _marker: PhantomData<*const Tokens<H, M>>,
 }
 
 unsafe impl<H: Envelope, M: Meta> Send for ViewOfQueue<H, M> {}
 
 impl<H: Envelope, M: Meta> Clone for ViewOfQueue<H, M> {
     fn clone(&self) -> Self {
-        Self { ptr: self.ptr }
+        Self {
+            #[cfg(feature = "resize")]
+            base: self.base,
+            #[cfg(not(feature = "resize"))]
+            slots: self.slots,
+            _marker: PhantomData,
+        }
     }
 }
 
-impl<H: Envelope, M: Meta> const Deref for ViewOfQueue<H, M> {
-    type Target = Tokens<H, M>;
-    fn deref(&self) -> &Self::Target {
-        // Safety: only view in TokenQueue context.
-        unsafe { self.ptr.as_ref() }
+impl<H: Envelope, M: Meta> ViewOfQueue<H, M> {
+    /// The slots of `queue`, the very queue this view is projected from.
+    #[inline]
+    fn slots<'a>(&'a self, queue: &'a TokenQueue<H, M>) -> &'a Tokens<H, M> {
+        #[cfg(feature = "resize")]
+        return queue.slots(self.base);
+        #[cfg(not(feature = "resize"))]
+        {
+            let _ = queue;
+            // Safety: the buffer never moves without resize, and lives as long as the queue.
+            unsafe { &*self.slots }
+        }
     }
 }
 
@@ -53,13 +79,47 @@ impl<H: Envelope, M: Meta> Clone for ViewOfDuplex<H, M> {
     }
 }
 
+/// Configuration of a [`TokenQueue`], or of both directions of a [`TokenDuplex`].
+///
+/// A bare capacity converts to the MPMC mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    pub cap: usize,
+    pub mode: Mode,
+}
+
+impl QueueConfig {
+    pub const fn mpmc(cap: usize) -> Self {
+        Self {
+            cap,
+            mode: Mode::Mpmc,
+        }
+    }
+
+    /// Each direction is owned by exactly one sender and one receiver, across all processes.
+    ///
+    /// Splitting a direction twice while its endpoints are alive, or cloning them, panics.
+    pub const fn spsc(cap: usize) -> Self {
+        Self {
+            cap,
+            mode: Mode::Spsc,
+        }
+    }
+}
+
+impl const From<usize> for QueueConfig {
+    fn from(cap: usize) -> Self {
+        Self::mpmc(cap)
+    }
+}
+
 pub trait AsTokenQueue<H: Envelope, M: Meta>: AsEntry<TokenQueue<H, M>> {}
 impl<H: Envelope, M: Meta, T: AsEntry<TokenQueue<H, M>>> AsTokenQueue<H, M> for T {}
 
 type QueueView<H, M, E> = EntryGuard<E, TokenQueue<H, M>, ViewOfQueue<H, M>>;
 pub struct TokenQueue<H: Envelope, M: Meta> {
     header: Header,
-    buf: TokenOfTokens<H, M>,
+    buf: UnsafeCell<TokenOfTokens<H, M>>,
 }
 
 unsafe impl<H: Send + Envelope, M: Meta> Send for TokenQueue<H, M> {}
@@ -71,32 +131,26 @@ impl<H: Envelope, M: Meta> RefUnwindSafe for TokenQueue<H, M> {}
 impl<H: Envelope, M: Meta> Finalize for TokenQueue<H, M> {
     fn finalize(&self) {
         // Recover the disconnection state for next preparation.
-        self.header.open()
+        self.header.reset()
     }
 }
 
 impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Resource<A> for TokenQueue<H, M> {
-    type Config = usize;
+    type Config = QueueConfig;
     fn new(conf: Self::Config, ctx: A) -> (Self, A) {
-        let cap = conf;
+        let QueueConfig { cap, mode } = conf;
         let alloc = ctx;
-        let header = Header::new(cap);
-        let buffer: PBox<_, A> = PBox::new_slice_in(
-            cap,
-            |i| Slot {
-                stamp: AtomicUsize::new(i),
-                value: UnsafeCell::new(MaybeUninit::uninit()),
-            },
-            alloc,
-        );
+        let header = Header::new(cap, mode);
+        let buffer: PBox<_, A> = PBox::new_slice_in(cap, Slot::vacant, alloc);
         let (buf, alloc) = buffer.token_of_with();
+        let buf = UnsafeCell::new(buf);
         (TokenQueue { header, buf }, alloc)
     }
 
     fn free(s: Self, ctx: A) -> A {
         let alloc = ctx;
         let Self { header: _, buf } = s;
-        let b = buf.boxed(alloc);
+        let b = buf.into_inner().boxed(alloc);
         PBox::drop_in(b)
     }
 }
@@ -107,8 +161,114 @@ impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Project<A> for TokenQueue<
     #[inline]
     fn project(&self, ctx: A) -> (Self::View, A) {
         let alloc = ctx;
-        let buf = self.buf.as_ptr(&alloc);
-        (ViewOfQueue { ptr: buf }, alloc)
+        let view = ViewOfQueue {
+            #[cfg(feature = "resize")]
+            base: alloc.base_ptr(),
+            #[cfg(not(feature = "resize"))]
+            slots: self.slots(alloc.base_ptr()),
+            _marker: PhantomData,
+        };
+        (view, alloc)
+    }
+}
+
+impl<H: Envelope, M: Meta> TokenQueue<H, M> {
+    /// Resolves the slots against the allocator `base` of this process.
+    #[inline]
+    fn slots(&self, base: *const u8) -> &Tokens<H, M> {
+        // Safety: the buffer is only replaced while the queue is quiesced, when no operation
+        // resolves it.
+        unsafe { (*self.buf.get()).recall(base).as_ref() }
+    }
+}
+
+/// The queue could not be resized, see [`TokenQueue::resize`].
+#[cfg(feature = "resize")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeError {
+    /// The new buffer could not be allocated.
+    Alloc,
+    /// Another resize of the queue is in progress.
+    Busy,
+    /// An operation on the queue was still in progress at the deadline.
+    Timeout,
+    /// The pending items do not fit in the new capacity, their number is reported.
+    Overflow(usize),
+}
+
+#[cfg(feature = "resize")]
+impl<H: Envelope, M: Meta> TokenQueue<H, M> {
+    /// Replaces the slots by `cap` new ones, carrying the pending tokens over in order.
+    ///
+    /// The queue is quiesced meanwhile: new operations of every process wait, and the ones in
+    /// progress, open [`SlotGuard`](crate::channel::SlotGuard)s included, are waited out for
+    /// `timeout` at most. Views resolve the slots on each operation, so the attached endpoints
+    /// move over as soon as the queue resumes, and the old buffer is freed right away.
+    pub fn resize<A: MemAllocator<Meta = M>>(
+        &self,
+        cap: usize,
+        alloc: &A,
+        timeout: Duration,
+    ) -> Result<(), ResizeError> {
+        Self::resize_all(&[self], cap, alloc, timeout)
+    }
+
+    /// Resizes every queue of `queues` to `cap` slots, or none of them.
+    ///
+    /// All of them are quiesced and checked before the first one is migrated.
+    fn resize_all<A: MemAllocator<Meta = M>>(
+        queues: &[&Self],
+        cap: usize,
+        alloc: &A,
+        timeout: Duration,
+    ) -> Result<(), ResizeError> {
+        assert!(cap > 0, "capacity must not zero");
+        let mut buffers = Vec::with_capacity(queues.len());
+        for _ in queues {
+            match PBox::try_new_slice_in(cap, Slot::vacant, alloc) {
+                Ok(buffer) => buffers.push(buffer),
+                Err(_) => {
+                    for buffer in buffers {
+                        PBox::drop_in(buffer);
+                    }
+                    return Err(ResizeError::Alloc);
+                }
+            }
+        }
+
+        let deadline = Instant::now().checked_add(timeout);
+        let quiesced = queues
+            .iter()
+            .map(|q| q.header.quiesce(deadline))
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|quiesced| match quiesced.iter().find(|q| q.len() > cap) {
+                Some(q) => Err(ResizeError::Overflow(q.len())),
+                None => Ok(quiesced),
+            });
+        let quiesced = match quiesced {
+            Ok(quiesced) => quiesced,
+            Err(e) => {
+                for buffer in buffers {
+                    PBox::drop_in(buffer);
+                }
+                return Err(e);
+            }
+        };
+
+        let mut olds = Vec::with_capacity(queues.len());
+        for ((queue, quiesced), buffer) in queues.iter().zip(&quiesced).zip(buffers) {
+            let old = queue.slots(alloc.base_ptr());
+            unsafe { quiesced.migrate(old, &buffer) }.expect("the pending items are checked");
+            let (buf, _) = buffer.token_of_with();
+            // Safety: no operation resolves the slots while quiesced.
+            olds.push(unsafe { queue.buf.get().replace(buf) });
+        }
+        drop(quiesced);
+
+        for old in olds {
+            PBox::drop_in(old.boxed(alloc));
+        }
+        Ok(())
     }
 }
 
@@ -122,7 +282,7 @@ impl<E: AsTokenQueue<H, M>, H: Envelope, M: Meta> Queue for QueueView<H, M, E> {
 
     #[inline]
     fn buf(&self) -> &[Slot<Self::Item>] {
-        &self.view
+        self.view.slots(self.as_ref())
     }
 }
 
@@ -162,13 +322,13 @@ impl<T: Clone, Role> Clone for Split<T, Role> {
 
 impl<H: Envelope, M: Meta> Finalize for TokenDuplex<H, M> {
     fn finalize(&self) {
-        self.left.header.open();
-        self.right.header.open();
+        self.left.header.reset();
+        self.right.header.reset();
     }
 }
 
 impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Resource<A> for TokenDuplex<H, M> {
-    type Config = usize;
+    type Config = QueueConfig;
 
     fn new(cfg: Self::Config, ctx: A) -> (Self, A) {
         let alloc = ctx;
@@ -195,6 +355,22 @@ impl<H: Envelope, A: MemAllocator<Meta = M>, M: Meta> Project<A> for TokenDuplex
     }
 }
 
+#[cfg(feature = "resize")]
+impl<H: Envelope, M: Meta> TokenDuplex<H, M> {
+    /// Resizes both directions to `cap` slots, see [`TokenQueue::resize`].
+    ///
+    /// Either both directions are resized, or none: a direction that cannot be resized leaves
+    /// the other one untouched.
+    pub fn resize<A: MemAllocator<Meta = M>>(
+        &self,
+        cap: usize,
+        alloc: &A,
+        timeout: Duration,
+    ) -> Result<(), ResizeError> {
+        TokenQueue::resize_all(&[&self.left, &self.right], cap, alloc, timeout)
+    }
+}
+
 impl<T, Role> const Deref for Split<T, Role> {
     type Target = T;
 
@@ -213,7 +389,7 @@ impl<E: AsTokenDuplex<H, M>, H: Envelope, M: Meta> Queue for Split<DuplexView<H,
 
     #[inline]
     fn buf(&self) -> &[Slot<Self::Item>] {
-        &self.view.left
+        self.view.left.slots(&self.as_ref().left)
     }
 }
 
@@ -229,7 +405,7 @@ impl<E: AsTokenDuplex<H, M>, H: Envelope, M: Meta> Queue for Split<DuplexView<H,
 
     #[inline]
     fn buf(&self) -> &[Slot<Self::Item>] {
-        &self.view.right
+        self.view.right.slots(&self.as_ref().right)
     }
 }
 
@@ -249,13 +425,21 @@ impl<E: AsTokenDuplex<H, M> + Clone, H: Envelope, M: Meta> DuplexView<H, M, E> {
         )
     }
 
-    pub fn lsplit(self) -> (Sender<H, M, E, Left>, Receiver<H, M, E, Right>) {
+    /// Attaches a sender to the left queue and a receiver to the right one.
+    ///
+    /// Fails if either queue is in SPSC mode and its side has an endpoint attached already.
+    #[allow(clippy::type_complexity)]
+    pub fn lsplit(self) -> Result<(Sender<H, M, E, Left>, Receiver<H, M, E, Right>), AttachError> {
         let (l, r) = Self::split(self);
-        (l.sender(), r.receiver())
+        attach_pair(l, r)
     }
 
-    pub fn rsplit(self) -> (Sender<H, M, E, Right>, Receiver<H, M, E, Left>) {
+    /// Attaches a sender to the right queue and a receiver to the left one.
+    ///
+    /// Fails if either queue is in SPSC mode and its side has an endpoint attached already.
+    #[allow(clippy::type_complexity)]
+    pub fn rsplit(self) -> Result<(Sender<H, M, E, Right>, Receiver<H, M, E, Left>), AttachError> {
         let (l, r) = Self::split(self);
-        (r.sender(), l.receiver())
+        attach_pair(r, l)
     }
 }
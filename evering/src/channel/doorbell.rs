@@ -0,0 +1,427 @@
+use core::mem::ManuallyDrop;
+use core::ptr;
+use core::task::Poll;
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+
+use nix::errno::Errno;
+use nix::unistd;
+use tokio::io::unix::AsyncFd;
+
+use crate::channel::cross::{AsTokenDuplex, DuplexView, Left, Right, Split};
+use crate::channel::notify::Park;
+use crate::channel::{
+    AsyncReceiver, AsyncSender, Notify, Queue, QueueChannel, QueueRx, QueueTx, Receiver, RecvError,
+    SendError, Sender, TryRecvError, TrySendError,
+};
+use crate::mem::Meta;
+use crate::msg::Envelope;
+use crate::os::unix::{UnixFd, recv_fds, send_fds};
+
+pub type DuplexBellTx<H, M, E, R> = BellTx<Split<DuplexView<H, M, E>, R>>;
+pub type DuplexBellRx<H, M, E, R> = BellRx<Split<DuplexView<H, M, E>, R>>;
+
+/// An eventfd rung by the producer of a queue direction.
+///
+/// The counter itself carries no data: it only tells the reactor of the consumer that the queue
+/// may have become readable. The fd is process-local, share it with the peer process alongside
+/// the memfd, e.g. by `fork` or [`DuplexBells::send`], and adopt it there with
+/// [`Doorbell::from_fd`].
+#[derive(Debug)]
+pub struct Doorbell {
+    fd: UnixFd<OwnedFd>,
+}
+
+impl Doorbell {
+    pub fn new() -> nix::Result<Self> {
+        UnixFd::eventfd().map(Self::from_fd)
+    }
+
+    /// Adopts an eventfd shared by the peer process.
+    pub fn from_fd(fd: UnixFd<OwnedFd>) -> Self {
+        Self { fd }
+    }
+
+    pub fn fd(&self) -> &UnixFd<OwnedFd> {
+        &self.fd
+    }
+
+    pub fn try_clone(&self) -> nix::Result<Self> {
+        self.fd.dup().map(Self::from_fd)
+    }
+
+    pub fn ring(&self) -> nix::Result<()> {
+        match unistd::write(self.fd.as_fd(), &1u64.to_ne_bytes()) {
+            // The counter is saturated, the consumer has plenty of pending rings.
+            Ok(_) | Err(Errno::EAGAIN) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Resets the counter, returning the number of rings since the last drain.
+    pub fn drain(&self) -> nix::Result<u64> {
+        let mut buf = [0u8; size_of::<u64>()];
+        match unistd::read(self.fd.as_fd(), &mut buf) {
+            Ok(_) => Ok(u64::from_ne_bytes(buf)),
+            Err(Errno::EAGAIN) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl AsFd for Doorbell {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for Doorbell {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_fd().as_raw_fd()
+    }
+}
+
+/// The doorbells of both directions of a [`TokenDuplex`](crate::channel::cross::TokenDuplex).
+///
+/// Unlike the duplex, the doorbells are not placed in shared memory: one process creates the
+/// pair and hands both fds to its peer, each side then splits its duplex view with them.
+#[derive(Debug)]
+pub struct DuplexBells {
+    left: Doorbell,
+    right: Doorbell,
+}
+
+impl DuplexBells {
+    pub fn new() -> nix::Result<Self> {
+        Ok(Self {
+            left: Doorbell::new()?,
+            right: Doorbell::new()?,
+        })
+    }
+
+    pub fn from_fds(left: UnixFd<OwnedFd>, right: UnixFd<OwnedFd>) -> Self {
+        Self {
+            left: Doorbell::from_fd(left),
+            right: Doorbell::from_fd(right),
+        }
+    }
+
+    /// Sends both fds over the connected unix socket `sock`, see [`send_fds`].
+    pub fn send(&self, sock: BorrowedFd<'_>) -> nix::Result<()> {
+        send_fds(sock, &[self.left.as_fd(), self.right.as_fd()])
+    }
+
+    /// Receives the fds sent by [`DuplexBells::send`] from the peer process.
+    pub fn recv(sock: BorrowedFd<'_>) -> nix::Result<Self> {
+        let mut fds = recv_fds(sock, 2)?.into_iter();
+        match (fds.next(), fds.next()) {
+            (Some(left), Some(right)) => Ok(Self::from_fds(left, right)),
+            _ => Err(Errno::EBADMSG),
+        }
+    }
+
+    pub fn left(&self) -> &Doorbell {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Doorbell {
+        &self.right
+    }
+
+    /// Splits as [`DuplexView::lsplit`], ringing the left bell and listening on the right one.
+    ///
+    /// Must be called within a tokio runtime.
+    #[allow(clippy::type_complexity)]
+    pub fn lsplit<E: AsTokenDuplex<H, M> + Clone, H: Envelope, M: Meta>(
+        &self,
+        duplex: DuplexView<H, M, E>,
+    ) -> io::Result<(DuplexBellTx<H, M, E, Left>, DuplexBellRx<H, M, E, Right>)> {
+        let (tx, rx) = duplex
+            .lsplit()
+            .map_err(|e| io::Error::new(io::ErrorKind::ResourceBusy, e))?;
+        let tx = tx.with_bell(self.left.try_clone()?);
+        let rx = rx.with_bell(self.right.try_clone()?)?;
+        Ok((tx, rx))
+    }
+
+    /// Splits as [`DuplexView::rsplit`], ringing the right bell and listening on the left one.
+    ///
+    /// Must be called within a tokio runtime.
+    #[allow(clippy::type_complexity)]
+    pub fn rsplit<E: AsTokenDuplex<H, M> + Clone, H: Envelope, M: Meta>(
+        &self,
+        duplex: DuplexView<H, M, E>,
+    ) -> io::Result<(DuplexBellTx<H, M, E, Right>, DuplexBellRx<H, M, E, Left>)> {
+        let (tx, rx) = duplex
+            .rsplit()
+            .map_err(|e| io::Error::new(io::ErrorKind::ResourceBusy, e))?;
+        let tx = tx.with_bell(self.right.try_clone()?);
+        let rx = rx.with_bell(self.left.try_clone()?)?;
+        Ok((tx, rx))
+    }
+}
+
+/// A sender ringing the doorbell of its consumer after a push, if the consumer sleeps.
+///
+/// The consumer is rung as well once the sender drops, which may close the queue.
+#[derive(Debug)]
+pub struct BellTx<T: Queue> {
+    tx: ManuallyDrop<QueueTx<T>>,
+    bell: Doorbell,
+}
+
+/// A receiver whose wakeups are driven by the tokio reactor through the doorbell.
+#[derive(Debug)]
+pub struct BellRx<T: Queue> {
+    rx: QueueRx<T>,
+    bell: AsyncFd<Doorbell>,
+}
+
+impl<T: Queue> QueueTx<T> {
+    pub fn with_bell(self, bell: Doorbell) -> BellTx<T> {
+        BellTx {
+            tx: ManuallyDrop::new(self),
+            bell,
+        }
+    }
+}
+
+impl<T: Queue> QueueRx<T> {
+    /// Registers the doorbell with the tokio reactor of the current runtime.
+    pub fn with_bell(self, bell: Doorbell) -> io::Result<BellRx<T>> {
+        let bell = AsyncFd::new(bell)?;
+        Ok(BellRx { rx: self, bell })
+    }
+}
+
+impl<T: Queue> BellTx<T> {
+    #[inline]
+    fn ring(&self) {
+        // Pairs with the registration of the consumer: either the bell is rung, or the consumer
+        // observes the push on its re-check.
+        if self.tx.tx.header().readable().is_listening() {
+            let _ = self.bell.ring();
+        }
+    }
+
+    #[inline]
+    pub fn try_send(&self, value: T::Item) -> Result<(), TrySendError<T::Item>> {
+        self.tx.try_send(value)?;
+        self.ring();
+        Ok(())
+    }
+
+    pub fn try_send_many<I>(&self, iter: &mut I) -> Result<usize, TrySendError<()>>
+    where
+        I: Iterator<Item = T::Item>,
+    {
+        let n = self.tx.try_send_many(iter)?;
+        self.ring();
+        Ok(n)
+    }
+
+    /// Sends an item, parking on the futex while the queue is full.
+    pub async fn send(&self, value: T::Item) -> Result<(), SendError<T::Item>> {
+        self.tx.send(value).await?;
+        self.ring();
+        Ok(())
+    }
+
+    /// Sends an item as [`QueueTx::send_or_else`], then rings the doorbell.
+    pub async fn send_or_else(
+        &self,
+        value: T::Item,
+        unsent: impl FnOnce(T::Item),
+    ) -> Result<(), SendError<T::Item>> {
+        self.tx.send_or_else(value, unsent).await?;
+        self.ring();
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> (QueueTx<T>, Doorbell) {
+        let this = ManuallyDrop::new(self);
+        // Safety: `this` is never used nor dropped again.
+        unsafe {
+            (
+                ManuallyDrop::into_inner(ptr::read(&this.tx)),
+                ptr::read(&this.bell),
+            )
+        }
+    }
+}
+
+impl<T: Queue> Drop for BellTx<T> {
+    fn drop(&mut self) {
+        // Safety: `tx` is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.tx) };
+        // Whichever sender is the last one has closed the queue by now, and the mapping may be
+        // gone already: ring without looking.
+        let _ = self.bell.ring();
+    }
+}
+
+impl<T: Queue> Sender for BellTx<T> {
+    type Item = T::Item;
+
+    type TryError = TrySendError<T::Item>;
+
+    #[inline(always)]
+    fn try_send(&self, item: Self::Item) -> Result<(), Self::TryError> {
+        self.try_send(item)
+    }
+}
+
+impl<T: Queue> AsyncSender for BellTx<T> {
+    type Error = SendError<T::Item>;
+
+    #[inline(always)]
+    fn send(&self, item: Self::Item) -> impl Future<Output = Result<(), Self::Error>> {
+        self.send(item)
+    }
+
+    #[inline(always)]
+    fn send_or_else(
+        &self,
+        item: Self::Item,
+        unsent: impl FnOnce(Self::Item),
+    ) -> impl Future<Output = Result<(), Self::Error>> {
+        self.send_or_else(item, unsent)
+    }
+}
+
+impl<T: Queue> QueueChannel for BellTx<T> {
+    type Handle = T;
+
+    #[inline(always)]
+    fn handle(&self) -> &Self::Handle {
+        &self.tx.tx
+    }
+
+    fn close(&self) {
+        self.tx.tx.header().close();
+        // Rings right away rather than through the watch of the consumer.
+        let _ = self.bell.ring();
+    }
+}
+
+/// Keeps the consumer listening on the [`Notify`] while it awaits the doorbell, and watching
+/// it for a close.
+struct Waiting<'a> {
+    notify: &'a Notify,
+    park: Park,
+}
+
+impl<'a> Waiting<'a> {
+    fn new(notify: &'a Notify) -> Self {
+        notify.listen();
+        Self {
+            notify,
+            park: Park::new(),
+        }
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.park.cancel(self.notify);
+        self.notify.unlisten();
+    }
+}
+
+impl<T: Queue> BellRx<T> {
+    #[inline]
+    pub fn try_recv(&self) -> Result<T::Item, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    #[inline]
+    pub fn try_recv_many(&self, buf: &mut Vec<T::Item>, max: usize) -> Result<usize, TryRecvError> {
+        self.rx.try_recv_many(buf, max)
+    }
+
+    /// Receives an item, awaiting the doorbell while the queue is empty.
+    ///
+    /// A queue closed without ringing, e.g. by a plain [`QueueTx`], still wakes the receiver
+    /// through the futex.
+    pub async fn recv(&self) -> Result<T::Item, RecvError> {
+        let mut polled = false;
+        let mut poll = || match self.rx.recv_waiting(&mut polled) {
+            Ok(item) => Some(Ok(item)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(RecvError)),
+        };
+
+        if let Some(res) = poll() {
+            return res;
+        }
+
+        let mut waiting = Waiting::new(self.rx.rx.header().readable());
+        core::future::poll_fn(|cx| {
+            loop {
+                if let Some(res) = poll() {
+                    return Poll::Ready(res);
+                }
+                match self.bell.poll_read_ready(cx) {
+                    Poll::Ready(Ok(mut guard)) => {
+                        let _ = guard.get_inner().drain();
+                        guard.clear_ready();
+                        continue;
+                    }
+                    // The reactor only fails once it is shut down, nothing will ring anymore.
+                    Poll::Ready(Err(_)) => return Poll::Ready(Err(RecvError)),
+                    Poll::Pending => {}
+                }
+                match waiting.park.watch(waiting.notify, cx, || {
+                    self.rx.probe();
+                }) {
+                    Poll::Ready(()) => continue,
+                    // A close racing the watch is seen on the re-check.
+                    Poll::Pending => return poll().map_or(Poll::Pending, Poll::Ready),
+                }
+            }
+        })
+        .await
+    }
+
+    pub fn into_inner(self) -> (QueueRx<T>, Doorbell) {
+        (self.rx, self.bell.into_inner())
+    }
+}
+
+impl<T: Queue> Receiver for BellRx<T> {
+    type Item = T::Item;
+
+    type TryError = TryRecvError;
+
+    #[inline(always)]
+    fn try_recv(&self) -> Result<Self::Item, Self::TryError> {
+        self.try_recv()
+    }
+
+    #[inline(always)]
+    fn try_recv_many(
+        &self,
+        buf: &mut Vec<Self::Item>,
+        max: usize,
+    ) -> Result<usize, Self::TryError> {
+        self.try_recv_many(buf, max)
+    }
+}
+
+impl<T: Queue> AsyncReceiver for BellRx<T> {
+    type Error = RecvError;
+
+    #[inline(always)]
+    fn recv(&self) -> impl Future<Output = Result<Self::Item, Self::Error>> {
+        self.recv()
+    }
+}
+
+impl<T: Queue> QueueChannel for BellRx<T> {
+    type Handle = T;
+
+    #[inline(always)]
+    fn handle(&self) -> &Self::Handle {
+        &self.rx.rx
+    }
+}
@@ -1,20 +1,31 @@
 use core::{
+    async_iter::AsyncIterator,
     cell::UnsafeCell,
     clone::Clone,
     future::Future,
-    mem::MaybeUninit,
+    mem::{self, MaybeUninit},
     ops::Deref,
     ptr,
-    sync::atomic::{AtomicU8, AtomicU32, AtomicUsize, Ordering, fence},
+    sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, AtomicUsize, Ordering, fence},
     task::{Context, Poll, Waker},
+    time::Duration,
 };
 
-use crate::{channel::QueueChannel, numeric::Id};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::{
+    channel::{QueueChannel, SendError, TryRecvError},
+    numeric::Id,
+};
 
 use crossbeam_utils::Backoff;
 
 mod state {
     // FREE -> WAKER -> COMPLETED -> FREE
+    // FREE | WAKER -> ABORTED -> FREE
     /// FREE: at initiation
     pub const FREE: u8 = 0;
     /// WAKER: with `waker`, without `payload`
@@ -23,10 +34,14 @@ mod state {
     pub const UPDATING: u8 = 2;
     /// COMPLETED: with `payload`, possibly with `waker`
     pub const COMPLETED: u8 = 3;
+    /// ABORTED: failed by the pool, without `waker` nor `payload`
+    pub const ABORTED: u8 = 4;
 }
 
 const HEAD: usize = Id::HEAD;
 const NONE: usize = Id::NONE;
+/// Initial room for a burst of completions.
+const BURST: usize = 64;
 
 #[repr(C)]
 struct Cache<T> {
@@ -51,15 +66,11 @@ impl<T> Cache<T> {
         }
     }
 
-    const fn array<const N: usize>() -> [Self; N] {
-        let mut arr = [const { Cache::null(NONE) }; N];
-        let mut i = HEAD;
-        while i < N - 1 {
-            arr[i] = Cache::null(i + 1);
-            i += 1
-        }
-
-        arr
+    /// Links `cap` entries into a free list starting at `HEAD`.
+    fn array(cap: usize) -> Box<[Self]> {
+        (HEAD..cap)
+            .map(|i| Cache::null(if i + 1 < cap { i + 1 } else { NONE }))
+            .collect()
     }
 
     unsafe fn drop_waker(&self) {
@@ -87,12 +98,6 @@ impl<T> Cache<T> {
         unsafe { val.assume_init() }
     }
 
-    unsafe fn drop_payload(&self) {
-        unsafe {
-            let _ = &(*self.payload.get()).assume_init_drop();
-        }
-    }
-
     /// return complete successfully or not.
     pub fn complete(&self, payload: T) -> bool {
         unsafe { self.replace_payload(payload) };
@@ -117,6 +122,41 @@ impl<T> Cache<T> {
         }
     }
 
+    /// Fails a pending entry, return whether a waker is woken.
+    ///
+    /// A payload completed meanwhile is kept, the op resolves to it.
+    fn abort(&self) -> bool {
+        let backoff = Backoff::new();
+        loop {
+            let cur = self.state.load(Ordering::Acquire);
+            match cur {
+                state::FREE | state::WAKER => {
+                    if self
+                        .state
+                        .compare_exchange_weak(
+                            cur,
+                            state::ABORTED,
+                            Ordering::AcqRel,
+                            Ordering::Relaxed,
+                        )
+                        .is_err()
+                    {
+                        continue;
+                    }
+                    if cur == state::FREE {
+                        return false;
+                    }
+                    let waker = unsafe { self.read_waker() };
+                    waker.wake_by_ref();
+                    unsafe { self.drop_waker() };
+                    return true;
+                }
+                state::UPDATING => backoff.snooze(),
+                _ => return false,
+            }
+        }
+    }
+
     pub fn poll(&self, ctx: &mut Context<'_>) -> Poll<T> {
         let backoff = Backoff::new();
 
@@ -194,6 +234,11 @@ impl<T> Cache<T> {
     }
 
     pub unsafe fn clean(&self) -> u32 {
+        unsafe { self.clean_with(drop) }
+    }
+
+    /// Cleans the entry like [`Cache::clean`], handing an unclaimed payload to `dispose`.
+    unsafe fn clean_with(&self, dispose: impl FnOnce(T)) -> u32 {
         let cur = self.state.load(Ordering::Acquire);
         let new_live = self.live.fetch_add(1, Ordering::AcqRel).wrapping_add(1);
         if cur == state::WAKER {
@@ -201,9 +246,7 @@ impl<T> Cache<T> {
                 self.drop_waker();
             }
         } else if cur == state::COMPLETED {
-            unsafe {
-                self.drop_payload();
-            }
+            dispose(unsafe { self.take_payload() });
         }
         self.state.store(state::FREE, Ordering::Release);
         new_live
@@ -214,51 +257,213 @@ impl<T> Cache<T> {
     }
 }
 
-pub struct Op<T, const N: usize, P: const Deref<Target = CachePool<T, N>>> {
+pub struct Op<T, P: const Deref<Target = CachePool<T>>> {
     pool: P,
     entry: ptr::NonNull<Cache<T>>,
     idx: usize,
+    /// Sent to the server, which is told of the cancellation if dropped before completion.
+    armed: bool,
 }
 
-unsafe impl<T: Send, const N: usize, P: const Deref<Target = CachePool<T, N>>> Send
-    for Op<T, N, P>
-{
-}
-unsafe impl<T, const N: usize, P: const Deref<Target = CachePool<T, N>>> Sync for Op<T, N, P> {}
+unsafe impl<T: Send, P: const Deref<Target = CachePool<T>>> Send for Op<T, P> {}
+unsafe impl<T, P: const Deref<Target = CachePool<T>>> Sync for Op<T, P> {}
 
-pub type RefOp<'a, T, const N: usize> = Op<T, N, &'a CachePool<T, N>>;
-pub type OwnOp<T, const N: usize> = Op<T, N, CachePoolHandle<T, N>>;
+pub type RefOp<'a, T> = Op<T, &'a CachePool<T>>;
+pub type OwnOp<T> = Op<T, CachePoolHandle<T>>;
+/// An [`OwnOp`] bounded by a deadline of `Tm`, see [`Sx::try_submit_with_deadline`].
+pub type TimeoutOp<T, Tm> = Timeout<OwnOp<T>, <Tm as Timer>::Sleep>;
 
-impl<T, const N: usize, P: const Deref<Target = CachePool<T, N>>> Future for Op<T, N, P> {
-    type Output = T;
+impl<T, P: const Deref<Target = CachePool<T>>> Future for Op<T, P> {
+    type Output = Result<T, OpError>;
 
     fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: only a flag is written, nothing is moved.
+        let this = unsafe { self.get_unchecked_mut() };
         // Safety: ensured by `pool` field
-        unsafe { self.entry.as_ref().poll(cx) }
+        let entry = unsafe { this.entry.as_ref() };
+        let poll = match entry.poll(cx) {
+            Poll::Ready(payload) => Poll::Ready(Ok(payload)),
+            // Checked once the waker is registered, so that the abort wakes it otherwise.
+            Poll::Pending => match this.pool.aborted() {
+                Some(error) => Poll::Ready(Err(error)),
+                // The server skipped the request.
+                None if entry.state() == state::ABORTED => Poll::Ready(Err(OpError::Cancelled)),
+                None => Poll::Pending,
+            },
+        };
+        if poll.is_ready() {
+            // Nobody is left to tell of a cancellation once failed.
+            this.armed = false;
+        }
+        poll
+    }
+}
+
+impl<T, P: const Deref<Target = CachePool<T>>> Op<T, P> {
+    /// Gives up on the response and tells the server, same as dropping the op.
+    ///
+    /// The cancel message is sent right away through the [`Sx`] of the pool. If the request
+    /// queue is full, or no `Sx` is left, it is sent ahead of the next request instead, or by
+    /// [`Sx::flush_cancel`].
+    #[inline]
+    pub fn cancel(self) {
+        drop(self)
     }
 }
 
-impl<T, const N: usize, P: const Deref<Target = CachePool<T, N>>> Drop for Op<T, N, P> {
+impl<T, P: const Deref<Target = CachePool<T>>> Drop for Op<T, P> {
     fn drop(&mut self) {
         // Safety: ensured by `pool` field
-        unsafe { self.entry.as_ref().clean() };
-        self.pool.push_free(self.idx)
+        let entry = unsafe { self.entry.as_ref() };
+        let pending = self.armed
+            && !matches!(entry.state(), state::COMPLETED | state::ABORTED)
+            && self.pool.aborted().is_none();
+        // A late response is outdated from now on.
+        unsafe { entry.clean_with(|payload| self.pool.dispose(payload)) };
+        if pending {
+            self.pool.send_cancel(self.idx)
+        } else {
+            self.pool.push_free(self.idx)
+        }
     }
 }
 
-impl<T, const N: usize, P: const Deref<Target = CachePool<T, N>>> PartialEq for Op<T, N, P> {
+impl<T, P: const Deref<Target = CachePool<T>>> PartialEq for Op<T, P> {
     fn eq(&self, other: &Self) -> bool {
         self.entry == other.entry && self.idx == other.idx
     }
 }
 
-pub struct CachePool<T, const N: usize> {
+// An op only points into its pool, never into itself, so it may move once polled.
+impl<T, P: const Deref<Target = CachePool<T>>> Unpin for Op<T, P> {}
+
+impl<T, P: const Deref<Target = CachePool<T>>> Op<T, P> {
+    /// Bounds the op by `timeout` from now, see [`Op::deadline`].
+    #[inline]
+    pub fn timeout<Tm: Timer>(self, timer: &Tm, timeout: Duration) -> Timeout<Self, Tm::Sleep> {
+        self.deadline(timer, timer.deadline(timeout))
+    }
+
+    /// Bounds the op by `deadline`, past which it resolves to [`OpError::Timeout`].
+    ///
+    /// The op is dropped once elapsed: its entry is reclaimed, a late response is outdated,
+    /// and the server is told of the cancellation.
+    #[inline]
+    pub fn deadline<Tm: Timer>(
+        self,
+        timer: &Tm,
+        deadline: Tm::Instant,
+    ) -> Timeout<Self, Tm::Sleep> {
+        Timeout {
+            op: Some(self),
+            sleep: timer.sleep_until(deadline),
+        }
+    }
+}
+
+/// The clock of deadlines, so that ops time out on any runtime.
+pub trait Timer {
+    type Instant: Copy;
+    type Sleep: Future<Output = ()>;
+
+    /// Returns the instant `timeout` from now.
+    fn deadline(&self, timeout: Duration) -> Self::Instant;
+
+    /// Returns a future resolving once `deadline` passes.
+    fn sleep_until(&self, deadline: Self::Instant) -> Self::Sleep;
+}
+
+/// The timer of the tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    type Instant = tokio::time::Instant;
+    type Sleep = tokio::time::Sleep;
+
+    #[inline]
+    fn deadline(&self, timeout: Duration) -> Self::Instant {
+        tokio::time::Instant::now() + timeout
+    }
+
+    #[inline]
+    fn sleep_until(&self, deadline: Self::Instant) -> Self::Sleep {
+        tokio::time::sleep_until(deadline)
+    }
+}
+
+/// Why an op resolved without its response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpError {
+    /// The peer is gone, see [`CachePool::abort`].
+    Disconnected,
+    /// The server skipped the request, e.g. a link of a broken chain, see [`serve_chain`], or
+    /// the client gave up on every op, see [`CachePool::abort`].
+    Cancelled,
+    /// The op did not complete before its deadline, see [`Op::deadline`].
+    Timeout,
+}
+
+/// An op bounded by a deadline, see [`Op::deadline`].
+///
+/// # Panics
+///
+/// Panics if polled again once resolved.
+#[derive(Debug)]
+pub struct Timeout<O, S> {
+    op: Option<O>,
+    sleep: S,
+}
+
+impl<T, O, S> Future for Timeout<O, S>
+where
+    O: Future<Output = Result<T, OpError>> + Unpin,
+    S: Future<Output = ()>,
+{
+    type Output = Result<T, OpError>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: `sleep` is never moved, `op` is `Unpin`.
+        let this = unsafe { self.get_unchecked_mut() };
+        let op = this.op.as_mut().expect("`Timeout` polled after completion");
+        if let Poll::Ready(output) = core::pin::Pin::new(op).poll(cx) {
+            this.op = None;
+            return Poll::Ready(output);
+        }
+        let sleep = unsafe { core::pin::Pin::new_unchecked(&mut this.sleep) };
+        match sleep.poll(cx) {
+            Poll::Ready(()) => {
+                this.op = None;
+                Poll::Ready(Err(OpError::Timeout))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+pub struct CachePool<T> {
     inits: AtomicUsize,
     free_head: AtomicUsize,
-    entries: [Cache<T>; N],
+    /// The entries of cancelled ops, held until the server is told.
+    cancel_head: AtomicUsize,
+    /// The sender telling the server of a cancellation right away, see [`Outlet`].
+    cancel_tx: spin::Mutex<Option<CancelTx>>,
+    entries: Box<[Cache<T>]>,
+    /// The chunks of the entries serving a [`StreamOp`].
+    streams: Box<[Stream<T>]>,
+    /// The owner of the payloads nobody awaits, see [`CachePoolHandle::bind`].
+    dead: spin::Once<Box<dyn DeadLetter<T> + Send + Sync>>,
+    /// The submitters waiting for a free entry, see [`Sx::submit`].
+    vacancy: spin::Mutex<Vec<Waker>>,
+    /// Whether `vacancy` holds a waker, so that freeing an entry skips the lock otherwise.
+    vacancy_waiting: AtomicBool,
+    /// Set once by [`CachePool::abort`].
+    aborted: spin::Once<OpError>,
 }
 
-impl<T, const N: usize> core::fmt::Debug for CachePool<T, N> {
+impl<T> core::fmt::Debug for CachePool<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let inits = self.inits.load(Ordering::Relaxed);
         f.debug_struct("CachePool")
@@ -268,18 +473,31 @@ impl<T, const N: usize> core::fmt::Debug for CachePool<T, N> {
     }
 }
 
-impl<T, const N: usize> CachePool<T, N> {
-    pub const fn new() -> Self {
+impl<T> CachePool<T> {
+    /// Creates a pool of `cap` entries, the limit of ops in flight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero.
+    pub fn new(cap: usize) -> Self {
+        assert!(cap > 0, "capacity must not zero");
         Self {
             inits: AtomicUsize::new(0),
             free_head: AtomicUsize::new(HEAD),
-            entries: const { Cache::array() },
+            cancel_head: AtomicUsize::new(NONE),
+            cancel_tx: spin::Mutex::new(None),
+            entries: Cache::array(cap),
+            streams: (0..cap).map(|_| Stream::new()).collect(),
+            dead: spin::Once::new(),
+            vacancy: spin::Mutex::new(Vec::new()),
+            vacancy_waiting: AtomicBool::new(false),
+            aborted: spin::Once::new(),
         }
     }
 
     #[inline]
-    pub const fn capacity(&self) -> usize {
-        N
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
     }
 
     #[inline]
@@ -287,45 +505,155 @@ impl<T, const N: usize> CachePool<T, N> {
         self.inits.load(Ordering::Relaxed)
     }
 
-    fn pop_free(&self) -> usize {
+    /// Hands a payload nobody awaits to the dead-letter handler, or drops it.
+    fn dispose(&self, payload: T) {
+        match self.dead.get() {
+            Some(dead) => dead.dispose(payload),
+            None => drop(payload),
+        }
+    }
+
+    fn pop_from(&self, list: &AtomicUsize) -> usize {
         let backoff = Backoff::new();
         loop {
-            let head = self.free_head.load(Ordering::Acquire);
+            let head = list.load(Ordering::Acquire);
             if head == NONE {
                 return NONE;
             }
 
             let next = self.entries[head].next_free.load(Ordering::Relaxed);
-            if self
-                .free_head
+            if list
                 .compare_exchange_weak(head, next, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                self.inits.fetch_add(1, Ordering::AcqRel);
                 return head;
             }
             backoff.snooze();
         }
     }
 
-    fn push_free(&self, idx: usize) {
+    fn push_onto(&self, list: &AtomicUsize, idx: usize) {
         let backoff = Backoff::new();
         loop {
-            let head = self.free_head.load(Ordering::Acquire);
+            let head = list.load(Ordering::Acquire);
             self.entries[idx].next_free.store(head, Ordering::Relaxed);
-            if self
-                .free_head
+            if list
                 .compare_exchange_weak(head, idx, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                self.inits.fetch_sub(1, Ordering::AcqRel);
                 return;
             }
             backoff.snooze();
         }
     }
 
+    fn pop_free(&self) -> usize {
+        let idx = self.pop_from(&self.free_head);
+        if idx != NONE {
+            self.inits.fetch_add(1, Ordering::AcqRel);
+        }
+        idx
+    }
+
+    fn push_free(&self, idx: usize) {
+        self.push_onto(&self.free_head, idx);
+        self.inits.fetch_sub(1, Ordering::AcqRel);
+        self.notify_vacancy();
+    }
+
+    /// Wakes the submitters waiting for a free entry, if any registered.
+    #[inline]
+    fn notify_vacancy(&self) {
+        // Pairs with the fence of `poll_vacant`: either the submitter sees the entry pushed on
+        // its re-check, or the flag is seen here.
+        fence(Ordering::SeqCst);
+        if self.vacancy_waiting.load(Ordering::Relaxed) {
+            self.wake_vacancy();
+        }
+    }
+
+    fn wake_vacancy(&self) {
+        let mut vacancy = self.vacancy.lock();
+        if !vacancy.is_empty() {
+            let wakers = mem::take(&mut *vacancy);
+            self.vacancy_waiting.store(false, Ordering::Relaxed);
+            drop(vacancy);
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    /// Fails every pending op with `error`, returning how many are woken.
+    ///
+    /// The pool stays aborted: no op is claimed from then on, and the streams end once their
+    /// buffered chunks are taken. A response already received is still handed to its op. Only
+    /// the first error is kept.
+    pub fn abort(&self, error: OpError) -> usize {
+        self.aborted.call_once(|| error);
+        let n = self.entries.iter().filter(|entry| entry.abort()).count();
+        self.streams.iter().for_each(Stream::wake);
+        self.wake_vacancy();
+        n
+    }
+
+    /// Refuses `item` for want of a free entry.
+    fn refuse<E>(&self, item: T) -> TrySubmitError<E, T> {
+        match self.aborted() {
+            Some(_) => TrySubmitError::Aborted(item),
+            None => TrySubmitError::CacheFull(item),
+        }
+    }
+
+    /// Returns the error the pool is aborted with, if any.
+    #[inline]
+    pub fn aborted(&self) -> Option<OpError> {
+        self.aborted.get().copied()
+    }
+
+    /// Tells the server that the op at `idx` is cancelled, right away unless the request queue
+    /// is full or no [`Sx`] is left, in which case its entry is held by [`CachePool::push_cancel`].
+    fn send_cancel(&self, idx: usize) {
+        let cancel_tx = self.cancel_tx.lock();
+        // Safety: the sender is withdrawn under the lock before it is dropped.
+        let sent = cancel_tx.is_some_and(|tx| unsafe { (tx.send)(tx.sender, self.cancelled(idx)) });
+        drop(cancel_tx);
+        if sent {
+            self.push_free(idx)
+        } else {
+            self.push_cancel(idx)
+        }
+    }
+
+    /// Holds the entry of a cancelled op until its cancel message is sent.
+    ///
+    /// The submitters waiting for a free entry are woken to send it, see [`Sx::submit`].
+    fn push_cancel(&self, idx: usize) {
+        self.push_onto(&self.cancel_head, idx);
+        self.notify_vacancy();
+    }
+
+    /// Takes the id of a cancelled request whose cancel message is not sent yet.
+    fn pop_cancel(&self) -> Option<Id> {
+        let idx = self.pop_from(&self.cancel_head);
+        if idx == NONE {
+            return None;
+        }
+        Some(self.cancelled(idx))
+    }
+
+    /// The id of the cancelled request of the entry at `idx`.
+    fn cancelled(&self, idx: usize) -> Id {
+        // The entry was cleaned once since the request was sent.
+        let live = self.entries[idx]
+            .live
+            .load(Ordering::Acquire)
+            .wrapping_sub(1);
+        Id { idx, live }
+    }
+
     fn prepare(&self) -> Option<(&Cache<T>, Id)> {
+        if self.aborted.is_completed() {
+            return None;
+        }
         let idx = self.pop_free();
         if idx == NONE {
             return None;
@@ -344,24 +672,50 @@ impl<T, const N: usize> CachePool<T, N> {
     }
 }
 
-impl<T, const N: usize> CachePool<T, N> {
-    pub fn probe(&self) -> Option<(RefOp<'_, T, N>, Id)> {
+impl<T> CachePool<T> {
+    pub fn probe(&self) -> Option<(RefOp<'_, T>, Id)> {
         let (entry, id) = self.prepare()?;
         Some((
             RefOp {
                 pool: self,
                 entry: entry.into(),
                 idx: id.idx,
+                armed: false,
             },
             id,
         ))
     }
 
-    fn complete(&self, id: Id, payload: T) -> TryCompState {
+    /// Completes the op `id` with a received item, see [`CachePool::complete_or`].
+    ///
+    /// A cancel message fails the op with [`OpError::Cancelled`], the server skipped its
+    /// request, see [`serve_chain`].
+    fn deliver<R: Identifier<T>>(&self, item: R, outdated: impl FnOnce(T)) -> TryCompState {
+        if let Some(id) = item.cancelled() {
+            return match self.lookup(id) {
+                Some(e) if e.abort() => TryCompState::Success,
+                Some(_) => TryCompState::Prefilled,
+                None => TryCompState::Outdated,
+            };
+        }
+        let end = item.is_end();
+        let (payload, id) = item.decompose();
+        self.complete_or(id, payload, end, outdated)
+    }
+
+    /// Completes the op `id`, or hands the payload to `outdated` if the op is gone.
+    ///
+    /// The payload of a stream is buffered as a chunk, the last one unless `end` is `false`.
+    fn complete_or(&self, id: Id, payload: T, end: bool, outdated: impl FnOnce(T)) -> TryCompState {
         // id must be bounded
         let Some(e) = self.lookup(id) else {
+            outdated(payload);
             return TryCompState::Outdated;
         };
+        let stream = &self.streams[id.idx];
+        if stream.open.load(Ordering::Acquire) {
+            return stream.push(e, id, payload, end, outdated);
+        }
         if e.complete(payload) {
             TryCompState::Success
         } else {
@@ -370,59 +724,304 @@ impl<T, const N: usize> CachePool<T, N> {
     }
 }
 
+/// The chunks received for the entry of a [`StreamOp`].
+struct Stream<T> {
+    /// The entry serves a stream, checked without the lock by the completer.
+    open: AtomicBool,
+    chunks: spin::Mutex<Chunks<T>>,
+}
+
+struct Chunks<T> {
+    buf: VecDeque<T>,
+    waker: Option<Waker>,
+    /// The last chunk is buffered.
+    ended: bool,
+}
+
+impl<T> Stream<T> {
+    fn new() -> Self {
+        Self {
+            open: AtomicBool::new(false),
+            chunks: spin::Mutex::new(Chunks {
+                buf: VecDeque::new(),
+                waker: None,
+                ended: false,
+            }),
+        }
+    }
+
+    fn push(
+        &self,
+        entry: &Cache<T>,
+        id: Id,
+        payload: T,
+        end: bool,
+        outdated: impl FnOnce(T),
+    ) -> TryCompState {
+        let mut chunks = self.chunks.lock();
+        // The stream may be dropped since the lookup.
+        if !self.open.load(Ordering::Relaxed) || entry.live.load(Ordering::Acquire) != id.live {
+            drop(chunks);
+            outdated(payload);
+            return TryCompState::Outdated;
+        }
+        chunks.buf.push_back(payload);
+        chunks.ended |= end;
+        let waker = chunks.waker.take();
+        drop(chunks);
+        match waker {
+            Some(waker) => {
+                waker.wake();
+                TryCompState::Success
+            }
+            None => TryCompState::Prefilled,
+        }
+    }
+
+    /// Wakes the stream to find the pool aborted.
+    fn wake(&self) {
+        let waker = self.chunks.lock().waker.take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Releases the entry of a dropped stream, a late chunk is outdated from now on.
+    ///
+    /// The chunks left are handed to `dispose`.
+    fn close(&self, entry: &Cache<T>, dispose: impl Fn(T)) {
+        let mut chunks = self.chunks.lock();
+        self.open.store(false, Ordering::Relaxed);
+        unsafe { entry.clean() };
+        let buf = mem::take(&mut chunks.buf);
+        chunks.waker = None;
+        chunks.ended = false;
+        drop(chunks);
+        buf.into_iter().for_each(dispose);
+    }
+}
+
+/// The chunks of a streamed response, see [`Sx::try_submit_stream`].
+///
+/// At most `window` chunks are buffered in the entry of the request: the server sends one chunk
+/// per credit, and the stream hands the credits back as its chunks are taken. The stream ends
+/// with the chunk not marked by [`Identified::compose_more`].
+pub struct StreamOp<'a, S: super::Sender, U>
+where
+    S::Item: Identifier<U>,
+{
+    sx: &'a Sx<S, U>,
+    id: Id,
+    window: u32,
+    /// The credits of the taken chunks, not handed back yet.
+    owed: u32,
+    ended: bool,
+    /// Sent to the server, which is told of the cancellation if dropped before the end.
+    armed: bool,
+}
+
+impl<S: super::Sender, U> StreamOp<'_, S, U>
+where
+    S::Item: Identifier<U>,
+{
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Receives the next chunk, or `None` once the stream ended, see [`AsyncIterator`].
+    pub fn next_item(&mut self) -> impl Future<Output = Option<U>> + '_ {
+        core::future::poll_fn(|cx| core::pin::Pin::new(&mut *self).poll_next(cx))
+    }
+
+    /// Hands the owed credits back, unless the request queue is full.
+    fn grant(&mut self) {
+        if let Some(msg) = <S::Item as Identifier<U>>::credit(self.id, self.owed)
+            && self.sx.sender.try_send(msg).is_err()
+        {
+            return;
+        }
+        self.owed = 0;
+    }
+}
+
+impl<S: super::Sender, U> AsyncIterator for StreamOp<'_, S, U>
+where
+    S::Item: Identifier<U>,
+{
+    type Item = U;
+
+    fn poll_next(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<U>> {
+        let this = self.get_mut();
+        if this.ended {
+            return Poll::Ready(None);
+        }
+        let stream = &this.sx.pool.streams[this.id.idx];
+        let mut chunks = stream.chunks.lock();
+        if let Some(chunk) = chunks.buf.pop_front() {
+            let last = chunks.ended && chunks.buf.is_empty();
+            drop(chunks);
+            if last {
+                this.ended = true;
+                this.armed = false;
+            } else {
+                this.owed += 1;
+                // Batch the credits, but keep half a window in flight.
+                if this.owed * 2 >= this.window {
+                    this.grant();
+                }
+            }
+            return Poll::Ready(Some(chunk));
+        }
+        match &mut chunks.waker {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            waker => *waker = Some(cx.waker().clone()),
+        }
+        // Checked once the waker is registered, so that the abort wakes it otherwise.
+        if this.sx.pool.aborted().is_some() {
+            drop(chunks);
+            this.ended = true;
+            this.armed = false;
+            return Poll::Ready(None);
+        }
+        drop(chunks);
+        if this.owed > 0 {
+            this.grant();
+            if this.owed > 0 {
+                // The server may starve without the credits, retry once the queue drains.
+                cx.waker().wake_by_ref();
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<S: super::Sender, U> Drop for StreamOp<'_, S, U>
+where
+    S::Item: Identifier<U>,
+{
+    fn drop(&mut self) {
+        let pool = &self.sx.pool;
+        pool.streams[self.id.idx].close(&pool.entries[self.id.idx], |chunk| pool.dispose(chunk));
+        if self.armed {
+            pool.send_cancel(self.id.idx)
+        } else {
+            pool.push_free(self.id.idx)
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct CachePoolHandle<T, const N: usize>(crate::counter::CounterOf<CachePool<T, N>>);
+pub struct CachePoolHandle<T>(crate::counter::CounterOf<CachePool<T>>);
 
-impl<T, const N: usize> const Deref for CachePoolHandle<T, N> {
-    type Target = CachePool<T, N>;
+impl<T> const Deref for CachePoolHandle<T> {
+    type Target = CachePool<T>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<T, const N: usize> Clone for CachePoolHandle<T, N> {
+impl<T> Clone for CachePoolHandle<T> {
     fn clone(&self) -> Self {
         Self(self.0.acquire())
     }
 }
 
-impl<T, const N: usize> Drop for CachePoolHandle<T, N> {
+impl<T> Drop for CachePoolHandle<T> {
     fn drop(&mut self) {
         unsafe { self.0.release() };
     }
 }
 
-impl<T, const N: usize> CachePoolHandle<T, N> {
-    pub fn new() -> Self {
-        let pool = CachePool::new();
+impl<T> CachePoolHandle<T> {
+    /// Creates a shared pool of `cap` entries, see [`CachePool::new`].
+    pub fn new(cap: usize) -> Self {
+        let pool = CachePool::new(cap);
         Self(crate::counter::CounterOf::suspend(pool))
     }
 
-    pub fn claim(&self) -> Option<(OwnOp<T, N>, Id)> {
+    pub fn claim(&self) -> Option<(OwnOp<T>, Id)> {
         let (entry, id) = self.0.prepare()?;
         Some((
             OwnOp {
                 pool: self.clone(),
                 entry: entry.into(),
                 idx: id.idx,
+                armed: false,
             },
             id,
         ))
     }
 
-    pub fn bind<S: super::Sender, R: super::Receiver>(
+    /// Claims a free entry like [`CachePoolHandle::claim`], or wakes `cx` once one is freed.
+    fn poll_claim(&self, cx: &mut Context<'_>) -> Poll<(OwnOp<T>, Id)> {
+        self.poll_vacant(cx, Self::claim)
+    }
+
+    /// Claims `n` free entries at once, or wakes `cx` once an entry is freed.
+    ///
+    /// Nothing is held while waiting, so that two chains never starve each other.
+    fn poll_claim_many(&self, cx: &mut Context<'_>, n: usize) -> Poll<Vec<(OwnOp<T>, Id)>> {
+        self.poll_vacant(cx, |pool| {
+            if pool.capacity().saturating_sub(pool.len()) < n {
+                return None;
+            }
+            let claimed = core::iter::from_fn(|| pool.claim())
+                .take(n)
+                .collect::<Vec<_>>();
+            // Another submitter took some, the others are released.
+            (claimed.len() == n).then_some(claimed)
+        })
+    }
+
+    fn poll_vacant<C>(&self, cx: &mut Context<'_>, claim: impl Fn(&Self) -> Option<C>) -> Poll<C> {
+        if let Some(claimed) = claim(self) {
+            return Poll::Ready(claimed);
+        }
+        {
+            let mut vacancy = self.vacancy.lock();
+            if !vacancy.iter().any(|w| w.will_wake(cx.waker())) {
+                vacancy.push(cx.waker().clone());
+            }
+            self.vacancy_waiting.store(true, Ordering::Relaxed);
+        }
+        fence(Ordering::SeqCst);
+        // An entry freed before the waker is registered wakes nobody.
+        match claim(self) {
+            Some(claimed) => Poll::Ready(claimed),
+            None => Poll::Pending,
+        }
+    }
+
+    /// Binds the pool to a channel, `dead` owns the payloads nobody awaits from now on.
+    ///
+    /// Such are the late responses of ops gone, the responses of ops dropped unclaimed and the
+    /// chunks of streams dropped before their end. Bind the allocator to free shared payloads,
+    /// or [`Discard`] to drop plain ones. Only the first handler bound to a pool is kept.
+    pub fn bind<S: super::Sender + Sync, R: super::Receiver, D>(
         self,
         sender: S,
         receiver: R,
-    ) -> (Sx<S, T, N>, Cx<R, T, N>)
+        dead: D,
+    ) -> (Sx<S, T>, Cx<R, T>)
     where
         S::Item: Identifier<T>,
         R::Item: Identifier<T>,
+        D: DeadLetter<T> + Send + Sync + 'static,
     {
-        let s = Sx {
+        self.dead.call_once(|| Box::new(dead));
+        let outlet = Arc::new(Outlet {
             sender,
             pool: self.clone(),
+        });
+        *self.cancel_tx.lock() = Some(CancelTx {
+            sender: (&outlet.sender as *const S).cast(),
+            send: send_cancel::<S, T>,
+        });
+        let s = Sx {
+            sender: outlet,
+            pool: self.clone(),
         };
         let c = Cx {
             receiver,
@@ -433,9 +1032,47 @@ impl<T, const N: usize> CachePoolHandle<T, N> {
 }
 
 #[derive(Debug)]
-pub enum TrySubmitError<E> {
+pub enum TrySubmitError<E, T> {
+    SendError(E),
+    /// Too many ops are in flight, the item is handed back.
+    CacheFull(T),
+    /// The pool is aborted, see [`CachePool::abort`], the item is handed back.
+    Aborted(T),
+}
+
+/// The error of [`Sx::submit`].
+#[derive(Debug)]
+pub enum SubmitError<E, T> {
     SendError(E),
-    CacheFull,
+    /// The pool is aborted, see [`CachePool::abort`], the item is handed back.
+    Aborted(T),
+}
+
+/// The owner of the payloads nobody awaits anymore, see [`CachePoolHandle::bind`].
+pub trait DeadLetter<T> {
+    fn dispose(&self, payload: T);
+}
+
+/// Frees the payload of a token without running its destructor, see [`Token::discard`].
+///
+/// [`Token::discard`]: crate::token::Token::discard
+impl<A: crate::mem::MemAllocator, H: crate::msg::Envelope>
+    DeadLetter<crate::token::PackToken<H, A::Meta>> for A
+{
+    fn dispose(&self, payload: crate::token::PackToken<H, A::Meta>) {
+        let (token, _) = payload.unpack();
+        token.discard(self);
+    }
+}
+
+/// Drops the payloads, for pools whose payloads own nothing shared.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Discard;
+
+impl<T> DeadLetter<T> for Discard {
+    fn dispose(&self, payload: T) {
+        drop(payload)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -446,16 +1083,90 @@ pub enum TryCompState {
 }
 
 pub trait Identified<U>: Sized {
+    /// Whether `U` can carry the cancellation of a request, see [`Identified::cancel`].
+    const CANCEL: bool = false;
+    /// Whether `U` can carry the chunks of a streamed response, see [`Identified::compose_more`].
+    const STREAM: bool = false;
+    /// Whether `U` can link a request to the next one, see [`Identified::compose_linked`].
+    const LINK: bool = false;
+
     fn compose(self, id: Id) -> U;
     fn decompose(output: U) -> (Self, Id);
+
+    /// Builds the message telling the server that the request `id` is cancelled.
+    fn cancel(id: Id) -> Option<U> {
+        let _ = id;
+        None
+    }
+
+    /// Returns the request cancelled by `output`, if it is a cancel message.
+    fn cancelled(output: &U) -> Option<Id> {
+        let _ = output;
+        None
+    }
+
+    /// Composes a chunk of a streamed response, followed by more chunks of the request `id`.
+    ///
+    /// Without [`Identified::STREAM`], every chunk ends its stream.
+    fn compose_more(self, id: Id) -> U {
+        self.compose(id)
+    }
+
+    /// Returns `true` unless more chunks of the same response follow `output`.
+    fn is_end(output: &U) -> bool {
+        let _ = output;
+        true
+    }
+
+    /// Builds the message granting `n` more chunks to the stream `id`, see [`StreamOp`].
+    fn credit(id: Id, n: u32) -> Option<U> {
+        let _ = (id, n);
+        None
+    }
+
+    /// Returns the stream and its granted chunks, if `output` is a credit message.
+    fn credited(output: &U) -> Option<(Id, u32)> {
+        let _ = output;
+        None
+    }
+
+    /// Composes a link of a chain, the request `next` being served only once this one succeeded.
+    ///
+    /// Without [`Identified::LINK`], every request is a chain of its own.
+    fn compose_linked(self, id: Id, next: Id) -> U {
+        let _ = next;
+        self.compose(id)
+    }
+
+    /// Returns the request linked to `output`, unless it is the last link of its chain.
+    fn linked(output: &U) -> Option<Id> {
+        let _ = output;
+        None
+    }
 }
 
 pub trait Identifier<T>: Sized {
+    const CANCEL: bool;
+    const STREAM: bool;
+    const LINK: bool;
+
     fn decompose(self) -> (T, Id);
     fn compose(origin: T, id: Id) -> Self;
+    fn cancel(id: Id) -> Option<Self>;
+    fn cancelled(&self) -> Option<Id>;
+    fn compose_more(origin: T, id: Id) -> Self;
+    fn is_end(&self) -> bool;
+    fn credit(id: Id, n: u32) -> Option<Self>;
+    fn credited(&self) -> Option<(Id, u32)>;
+    fn compose_linked(origin: T, id: Id, next: Id) -> Self;
+    fn linked(&self) -> Option<Id>;
 }
 
 impl<T: Identified<U>, U> Identifier<T> for U {
+    const CANCEL: bool = T::CANCEL;
+    const STREAM: bool = T::STREAM;
+    const LINK: bool = T::LINK;
+
     fn decompose(self) -> (T, Id) {
         T::decompose(self)
     }
@@ -463,30 +1174,150 @@ impl<T: Identified<U>, U> Identifier<T> for U {
     fn compose(origin: T, id: Id) -> Self {
         T::compose(origin, id)
     }
-}
 
-pub trait Submitter<Op: Future, U> {
-    type Item: Identifier<U>;
-    type Error;
-    fn try_submit(&self, item: U) -> Result<Op, Self::Error>;
-}
+    fn cancel(id: Id) -> Option<Self> {
+        T::cancel(id)
+    }
 
-pub trait Completer<U> {
-    type Item: Identifier<U>;
-    type Error;
-    fn complete(&self) -> Result<TryCompState, Self::Error>;
-}
+    fn cancelled(&self) -> Option<Id> {
+        T::cancelled(self)
+    }
 
-#[derive(Debug)]
-pub struct Sx<S: super::Sender, U, const N: usize>
-where
-    S::Item: Identifier<U>,
-{
+    fn compose_more(origin: T, id: Id) -> Self {
+        T::compose_more(origin, id)
+    }
+
+    fn is_end(&self) -> bool {
+        T::is_end(self)
+    }
+
+    fn credit(id: Id, n: u32) -> Option<Self> {
+        T::credit(id, n)
+    }
+
+    fn credited(&self) -> Option<(Id, u32)> {
+        T::credited(self)
+    }
+
+    fn compose_linked(origin: T, id: Id, next: Id) -> Self {
+        T::compose_linked(origin, id, next)
+    }
+
+    fn linked(&self) -> Option<Id> {
+        T::linked(self)
+    }
+}
+
+/// A plain value paired with its id, as carried by an in-process [`local`](super::local) channel.
+impl<T> Identified<(T, Id)> for T {
+    fn compose(self, id: Id) -> (T, Id) {
+        (self, id)
+    }
+
+    fn decompose(output: (T, Id)) -> (Self, Id) {
+        output
+    }
+}
+
+pub trait Submitter<Op: Future, U> {
+    type Item: Identifier<U>;
+    type Error;
+    fn try_submit(&self, item: U) -> Result<Op, Self::Error>;
+}
+
+/// A [`Submitter`] which parks until the item can be submitted, rather than failing.
+pub trait AsyncSubmitter<Op: Future, U>: Submitter<Op, U> {
+    type AsyncError;
+
+    fn submit(&self, item: U) -> impl Future<Output = Result<Op, Self::AsyncError>>;
+}
+
+pub trait Completer<U> {
+    type Item: Identifier<U>;
+    type Error;
+    fn complete(&self) -> Result<TryCompState, Self::Error>;
+
+    /// Completes at most `max` received items, failing only if none is received.
+    fn complete_many(&self, max: usize) -> Result<usize, Self::Error> {
+        let mut n = 0;
+        while n < max {
+            match self.complete() {
+                Ok(_) => n += 1,
+                Err(e) if n == 0 => return Err(e),
+                Err(_) => break,
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// The submitting half of a bound pool, see [`CachePoolHandle::bind`].
+///
+/// The clones share the sender.
+#[derive(Debug)]
+pub struct Sx<S: super::Sender, U>
+where
+    S::Item: Identifier<U>,
+{
+    sender: Arc<Outlet<S, U>>,
+    pool: CachePoolHandle<U>,
+}
+
+/// The sender of the [`Sx`]s of a pool, through which a dropped op tells the server of its
+/// cancellation right away, as long as one of them is alive.
+#[derive(Debug)]
+struct Outlet<S, U> {
     sender: S,
-    pool: CachePoolHandle<U, N>,
+    pool: CachePoolHandle<U>,
+}
+
+impl<S, U> Deref for Outlet<S, U> {
+    type Target = S;
+
+    #[inline]
+    fn deref(&self) -> &S {
+        &self.sender
+    }
+}
+
+impl<S, U> Drop for Outlet<S, U> {
+    fn drop(&mut self) {
+        let sender = (&self.sender as *const S).cast();
+        let mut cancel_tx = self.pool.cancel_tx.lock();
+        if cancel_tx.is_some_and(|tx| tx.sender == sender) {
+            *cancel_tx = None;
+        }
+    }
+}
+
+/// The sender of an [`Outlet`] with its type erased, see [`CachePool::send_cancel`].
+#[derive(Clone, Copy)]
+struct CancelTx {
+    sender: *const (),
+    send: unsafe fn(*const (), Id) -> bool,
 }
 
-impl<S: super::Sender + Clone, U, const N: usize> Clone for Sx<S, U, N>
+// Safety: the sender is `Sync`, see [`CachePoolHandle::bind`].
+unsafe impl Send for CancelTx {}
+
+/// Sends the cancel message of `id` through `sender`, returning `false` if the queue is full.
+///
+/// # Safety
+///
+/// `sender` must point to a live `S`.
+unsafe fn send_cancel<S: super::Sender, U>(sender: *const (), id: Id) -> bool
+where
+    S::Item: Identifier<U>,
+{
+    let sender = unsafe { &*sender.cast::<S>() };
+    match <S::Item as Identifier<U>>::cancel(id) {
+        Some(msg) => sender.try_send(msg).is_ok(),
+        // Nothing to tell the server.
+        None => true,
+    }
+}
+
+impl<S: super::Sender, U> Clone for Sx<S, U>
 where
     S::Item: Identifier<U>,
 {
@@ -498,24 +1329,104 @@ where
     }
 }
 
-impl<S: super::Sender, U, const N: usize> Sx<S, U, N>
+impl<S: super::Sender, U> Sx<S, U>
 where
     S::Item: Identifier<U>,
 {
     pub fn try_submit_ref<'a>(
         &'a self,
         item: U,
-    ) -> Result<RefOp<'a, U, N>, TrySubmitError<S::TryError>> {
-        let (op, id) = self.pool.0.probe().ok_or(TrySubmitError::CacheFull)?;
-        let msg = S::Item::compose(item, id);
+    ) -> Result<RefOp<'a, U>, TrySubmitError<S::TryError, U>> {
+        self.flush_cancel();
+        let Some((mut op, id)) = self.pool.0.probe() else {
+            return Err(self.pool.refuse(item));
+        };
+        let msg = <S::Item as Identifier<U>>::compose(item, id);
+        self.sender
+            .try_send(msg)
+            .map_err(TrySubmitError::SendError)?;
+        op.armed = <S::Item as Identifier<U>>::CANCEL;
+        Ok(op)
+    }
+
+    /// Sends the cancel messages held back by a full queue, see [`Op::cancel`], returning how
+    /// many.
+    ///
+    /// Stops early if the queue is full, the rest are sent on a later call.
+    pub fn flush_cancel(&self) -> usize {
+        let mut n = 0;
+        while let Some(id) = self.pool.pop_cancel() {
+            let Some(msg) = <S::Item as Identifier<U>>::cancel(id) else {
+                // Nothing to tell the server.
+                self.pool.push_free(id.idx);
+                continue;
+            };
+            if self.sender.try_send(msg).is_err() {
+                self.pool.push_cancel(id.idx);
+                break;
+            }
+            self.pool.push_free(id.idx);
+            n += 1;
+        }
+        n
+    }
+}
+
+impl<S: super::Sender, U> Sx<S, U>
+where
+    S::Item: Identifier<U>,
+{
+    /// Submits `item` for a streamed response, granting the server `window` chunks ahead.
+    ///
+    /// The stream holds a single entry of the pool whatever the length of the response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is zero.
+    pub fn try_submit_stream(
+        &self,
+        item: U,
+        window: u32,
+    ) -> Result<StreamOp<'_, S, U>, TrySubmitError<S::TryError, U>> {
+        assert!(window > 0, "window must not zero");
+        self.flush_cancel();
+        let Some((_, id)) = self.pool.prepare() else {
+            return Err(self.pool.refuse(item));
+        };
+        self.pool.streams[id.idx]
+            .open
+            .store(true, Ordering::Release);
+        let mut op = StreamOp {
+            sx: self,
+            id,
+            window,
+            owed: window,
+            ended: false,
+            armed: false,
+        };
+        let msg = <S::Item as Identifier<U>>::compose(item, id);
         self.sender
             .try_send(msg)
             .map_err(TrySubmitError::SendError)?;
+        op.armed = <S::Item as Identifier<U>>::CANCEL;
+        // The first window follows the request, or the first poll of the stream.
+        op.grant();
         Ok(op)
     }
+
+    /// Submits `item` with an op bounded by `deadline`, see [`Op::deadline`].
+    pub fn try_submit_with_deadline<Tm: Timer>(
+        &self,
+        item: U,
+        timer: &Tm,
+        deadline: Tm::Instant,
+    ) -> Result<TimeoutOp<U, Tm>, TrySubmitError<S::TryError, U>> {
+        let op = self.try_submit(item)?;
+        Ok(op.deadline(timer, deadline))
+    }
 }
 
-impl<S: super::Sender + QueueChannel, U, const N: usize> super::QueueChannel for Sx<S, U, N>
+impl<S: super::Sender + QueueChannel, U> super::QueueChannel for Sx<S, U>
 where
     S::Item: Identifier<U>,
 {
@@ -527,34 +1438,247 @@ where
     }
 }
 
-impl<'a, S: super::Sender, U, const N: usize> Submitter<OwnOp<U, N>, U> for Sx<S, U, N>
+impl<'a, S: super::Sender, U> Submitter<OwnOp<U>, U> for Sx<S, U>
 where
     S::Item: Identifier<U>,
 {
     type Item = S::Item;
 
-    type Error = TrySubmitError<S::TryError>;
+    type Error = TrySubmitError<S::TryError, U>;
 
-    fn try_submit(&self, item: U) -> Result<OwnOp<U, N>, Self::Error> {
-        let (op, id) = self.pool.claim().ok_or(TrySubmitError::CacheFull)?;
-        let msg = S::Item::compose(item, id);
+    fn try_submit(&self, item: U) -> Result<OwnOp<U>, Self::Error> {
+        self.flush_cancel();
+        let Some((mut op, id)) = self.pool.claim() else {
+            return Err(self.pool.refuse(item));
+        };
+        let msg = <S::Item as Identifier<U>>::compose(item, id);
         self.sender
             .try_send(msg)
             .map_err(TrySubmitError::SendError)?;
+        op.armed = <S::Item as Identifier<U>>::CANCEL;
         Ok(op)
     }
 }
 
+impl<S: super::AsyncSender, U> AsyncSubmitter<OwnOp<U>, U> for Sx<S, U>
+where
+    S::Item: Identifier<U>,
+{
+    type AsyncError = SubmitError<S::Error, U>;
+
+    #[inline]
+    fn submit(&self, item: U) -> impl Future<Output = Result<OwnOp<U>, Self::AsyncError>> {
+        Sx::submit(self, item)
+    }
+}
+
+impl<S: super::AsyncSender, U> Sx<S, U>
+where
+    S::Item: Identifier<U>,
+{
+    /// Submits `item`, parking until both an entry of the pool and a slot of the queue are
+    /// free.
+    ///
+    /// The submitter is woken by an op dropped, i.e. claimed or abandoned, for an entry, and
+    /// by the receiver as [`AsyncSender::send`](super::AsyncSender::send) does for a slot. The
+    /// item is handed to the [`DeadLetter`] of the pool if the future is dropped before it
+    /// resolves, and handed back once the pool is aborted.
+    pub async fn submit(&self, item: U) -> Result<OwnOp<U>, SubmitError<S::Error, U>> {
+        let mut unsent = Unsent {
+            pool: &self.pool,
+            items: Some(item).into_iter(),
+        };
+        let claimed = self.vacant(|pool, cx| pool.poll_claim(cx)).await;
+        let item = unsent.items.next().expect("the item is not sent yet");
+        let Some((mut op, id)) = claimed else {
+            return Err(SubmitError::Aborted(item));
+        };
+        let msg = <S::Item as Identifier<U>>::compose(item, id);
+        self.sender
+            .send_or_else(msg, |msg| {
+                self.pool
+                    .dispose(<S::Item as Identifier<U>>::decompose(msg).0)
+            })
+            .await
+            .map_err(SubmitError::SendError)?;
+        op.armed = <S::Item as Identifier<U>>::CANCEL;
+        Ok(op)
+    }
+
+    /// Starts a chain of linked requests, see [`ChainBuilder`].
+    #[inline]
+    pub fn chain(&self) -> ChainBuilder<'_, S, U> {
+        ChainBuilder {
+            sx: self,
+            items: Vec::new(),
+        }
+    }
+
+    /// Claims entries with `claim` once free, or returns `None` once the pool is aborted.
+    ///
+    /// The entries held for the cancel messages are freed meanwhile, parking on the queue
+    /// until it has room for them.
+    async fn vacant<C>(
+        &self,
+        claim: impl Fn(&CachePoolHandle<U>, &mut Context<'_>) -> Poll<C>,
+    ) -> Option<C> {
+        loop {
+            let held = core::future::poll_fn(|cx| {
+                self.flush_cancel();
+                if let Poll::Ready(claimed) = claim(&self.pool, cx) {
+                    return Poll::Ready(Ok(claimed));
+                }
+                if self.pool.aborted().is_some() {
+                    return Poll::Ready(Err(None));
+                }
+                match self.pool.pop_cancel() {
+                    Some(id) => Poll::Ready(Err(Some(id))),
+                    None => Poll::Pending,
+                }
+            })
+            .await;
+            match held {
+                Ok(claimed) => return Some(claimed),
+                Err(None) => return None,
+                Err(Some(id)) => self.send_held_cancel(id).await,
+            }
+        }
+    }
+
+    /// Sends the cancel message held for the entry of `id`, then frees the entry.
+    ///
+    /// The entry is held again if the future is dropped before the message is sent, and freed
+    /// if the queue is disconnected since nobody is left to tell.
+    async fn send_held_cancel(&self, id: Id) {
+        if let Some(msg) = <S::Item as Identifier<U>>::cancel(id) {
+            let _ = self
+                .sender
+                .send_or_else(msg, |_| self.pool.push_cancel(id.idx))
+                .await;
+        }
+        self.pool.push_free(id.idx);
+    }
+}
+
+/// A chain of linked requests, each served only once the previous one succeeded.
+///
+/// Like the linked submissions of io_uring, a link failing breaks the chain and the links after
+/// it are skipped. Every link but the last carries the id of the next one, so that the server
+/// finds the chain back whatever it receives meanwhile, see [`Links`] and [`serve_chain`].
+pub struct ChainBuilder<'a, S: super::Sender, U>
+where
+    S::Item: Identifier<U>,
+{
+    sx: &'a Sx<S, U>,
+    items: Vec<U>,
+}
+
+impl<S: super::AsyncSender, U> ChainBuilder<'_, S, U>
+where
+    S::Item: Identifier<U>,
+{
+    /// Appends `item` to the chain.
+    #[inline]
+    pub fn link(mut self, item: U) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Submits the links in order, parking like [`Sx::submit`] until an entry is free for each.
+    ///
+    /// The entries are claimed at once. The links not sent yet are handed to the [`DeadLetter`]
+    /// of the pool if the queue is disconnected or the future is dropped before it resolves,
+    /// and handed back once the pool is aborted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the chain is longer than the capacity of the pool.
+    pub async fn submit(self) -> Result<Chain<U>, SubmitError<S::Error, Vec<U>>> {
+        let Self { sx, items } = self;
+        let n = items.len();
+        assert!(n <= sx.pool.capacity(), "chain must fit in the pool");
+        let mut unsent = Unsent {
+            pool: &sx.pool,
+            items: items.into_iter(),
+        };
+        let Some(claimed) = sx.vacant(|pool, cx| pool.poll_claim_many(cx, n)).await else {
+            return Err(SubmitError::Aborted(unsent.items.by_ref().collect()));
+        };
+        let ids = claimed.iter().map(|(_, id)| *id).collect::<Vec<_>>();
+        let mut ops = Vec::with_capacity(n);
+        for (i, (mut op, id)) in claimed.into_iter().enumerate() {
+            let item = unsent.items.next().expect("an entry is claimed per link");
+            let msg = match ids.get(i + 1) {
+                Some(&next) => <S::Item as Identifier<U>>::compose_linked(item, id, next),
+                None => <S::Item as Identifier<U>>::compose(item, id),
+            };
+            sx.sender
+                .send_or_else(msg, |msg| {
+                    sx.pool
+                        .dispose(<S::Item as Identifier<U>>::decompose(msg).0)
+                })
+                .await
+                .map_err(SubmitError::SendError)?;
+            op.armed = <S::Item as Identifier<U>>::CANCEL;
+            ops.push(op);
+        }
+        Ok(Chain {
+            ops,
+            results: Vec::with_capacity(n),
+        })
+    }
+}
+
+/// The items not sent yet by [`Sx::submit`] or [`ChainBuilder::submit`], handed to the
+/// [`DeadLetter`] of the pool on drop.
+struct Unsent<'a, U, I: Iterator<Item = U>> {
+    pool: &'a CachePool<U>,
+    items: I,
+}
+
+impl<U, I: Iterator<Item = U>> Drop for Unsent<'_, U, I> {
+    fn drop(&mut self) {
+        self.items.by_ref().for_each(|item| self.pool.dispose(item));
+    }
+}
+
+/// The responses of a chain, see [`ChainBuilder::submit`].
+///
+/// Resolves once every link is answered, in the order of the chain. The links skipped by a
+/// broken chain fail with [`OpError::Cancelled`].
+pub struct Chain<U> {
+    ops: Vec<OwnOp<U>>,
+    results: Vec<Result<U, OpError>>,
+}
+
+// The responses are never pinned.
+impl<U> Unpin for Chain<U> {}
+
+impl<U> Future for Chain<U> {
+    type Output = Vec<Result<U, OpError>>;
+
+    fn poll(self: core::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        while let Some(op) = this.ops.get_mut(this.results.len()) {
+            match core::pin::Pin::new(op).poll(cx) {
+                Poll::Ready(result) => this.results.push(result),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(mem::take(&mut this.results))
+    }
+}
+
 #[derive(Debug)]
-pub struct Cx<R: super::Receiver, U, const N: usize>
+pub struct Cx<R: super::Receiver, U>
 where
     R::Item: Identifier<U>,
 {
     receiver: R,
-    pool: CachePoolHandle<U, N>,
+    pool: CachePoolHandle<U>,
 }
 
-impl<R: super::Receiver + QueueChannel + Clone, U, const N: usize> Clone for Cx<R, U, N>
+impl<R: super::Receiver + QueueChannel + Clone, U> Clone for Cx<R, U>
 where
     R::Item: Identifier<U>,
 {
@@ -566,7 +1690,7 @@ where
     }
 }
 
-impl<R: super::Receiver + QueueChannel, U, const N: usize> super::QueueChannel for Cx<R, U, N>
+impl<R: super::Receiver + QueueChannel, U> super::QueueChannel for Cx<R, U>
 where
     R::Item: Identifier<U>,
 {
@@ -578,21 +1702,484 @@ where
     }
 }
 
-impl<'a, R: super::Receiver, U, const N: usize> Completer<U> for Cx<R, U, N>
+/// Aborts the pending ops of the pool once the peer is gone, see [`CachePool::abort`].
+impl<'a, R: super::Receiver<TryError = TryRecvError>, U> Completer<U> for Cx<R, U>
 where
     R::Item: Identifier<U>,
 {
     type Item = R::Item;
 
-    type Error = R::TryError;
+    type Error = TryRecvError;
 
     fn complete(&self) -> Result<TryCompState, Self::Error> {
-        let msg = self.receiver.try_recv()?;
-        let (payload, id) = msg.decompose();
-        Ok(self.pool.0.complete(id, payload))
+        let msg = self
+            .receiver
+            .try_recv()
+            .inspect_err(|e| self.disconnect(e))?;
+        Ok(self.pool.0.deliver(msg, |p| self.pool.dispose(p)))
+    }
+
+    fn complete_many(&self, max: usize) -> Result<usize, Self::Error> {
+        let mut burst = Vec::with_capacity(max.min(BURST));
+        let n = self
+            .receiver
+            .try_recv_many(&mut burst, max)
+            .inspect_err(|e| self.disconnect(e))?;
+        for msg in burst {
+            self.pool.0.deliver(msg, |p| self.pool.dispose(p));
+        }
+        Ok(n)
+    }
+}
+
+impl<R: super::Receiver<TryError = TryRecvError>, U> Cx<R, U>
+where
+    R::Item: Identifier<U>,
+{
+    /// Completes one op like [`Completer::complete`], handing a late response to `outdated`
+    /// instead of the [`DeadLetter`] of the pool.
+    ///
+    /// The op of a late response is gone, timed out or dropped, so nobody else frees its
+    /// payload.
+    pub fn complete_with(&self, outdated: impl FnOnce(U)) -> Result<TryCompState, TryRecvError> {
+        let msg = self
+            .receiver
+            .try_recv()
+            .inspect_err(|e| self.disconnect(e))?;
+        Ok(self.pool.0.deliver(msg, outdated))
+    }
+
+    fn disconnect(&self, error: &TryRecvError) {
+        if let TryRecvError::Disconnected = error {
+            self.pool.abort(OpError::Disconnected);
+        }
+    }
+}
+
+impl<R: super::AsyncReceiver<TryError = TryRecvError>, U> Cx<R, U>
+where
+    R::Item: Identifier<U>,
+{
+    /// Completes the ops of the pool until the peer disconnects, returning how many.
+    ///
+    /// The reactor sleeps in [`AsyncReceiver::recv`](super::AsyncReceiver::recv), i.e. on the
+    /// doorbell of a `BellRx` or the futex of a [`QueueRx`](super::QueueRx), and drains every
+    /// ready response once woken. Spawn it right after [`CachePoolHandle::bind`], the
+    /// submitters then only await their ops.
+    ///
+    /// Once the peer is gone, the ops still pending fail with [`OpError::Disconnected`].
+    pub async fn drive(self) -> usize {
+        let mut n = 0;
+        while let Ok(msg) = self.receiver.recv().await {
+            self.pool.0.deliver(msg, |p| self.pool.dispose(p));
+            // A disconnection is reported again by the next `recv`.
+            n += 1 + self.complete_many(usize::MAX).unwrap_or(0);
+        }
+        self.pool.abort(OpError::Disconnected);
+        n
+    }
+}
+
+/// The cancellations received by a server, indexed like the [`CachePool`] of its client.
+///
+/// Each slot records the last cancelled request of its entry. Entries are reused with a new
+/// `live`, so a cancellation never leaks into a later request. The credits granted to the
+/// streamed responses are kept alike, see [`StreamOp`].
+#[derive(Debug)]
+pub struct CancelSet {
+    slots: Box<[AtomicU64]>,
+    credits: Box<[Credit]>,
+}
+
+/// Marks a slot of [`CancelSet`] as holding a cancellation, the low half is its `live`.
+const CANCELLED: u64 = 1 << 32;
+
+/// The credits of a stream, the high half of `grant` is its `live`.
+#[derive(Debug)]
+struct Credit {
+    grant: AtomicU64,
+    waker: spin::Mutex<Option<Waker>>,
+}
+
+impl Credit {
+    fn wake(&self) {
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+}
+
+impl CancelSet {
+    /// Creates a set for a client pool of `cap` entries.
+    pub fn new(cap: usize) -> Self {
+        Self {
+            slots: (0..cap).map(|_| AtomicU64::new(0)).collect(),
+            credits: (0..cap)
+                .map(|_| Credit {
+                    grant: AtomicU64::new(0),
+                    waker: spin::Mutex::new(None),
+                })
+                .collect(),
+        }
+    }
+
+    /// Sorts a received item, applying a cancel or credit message or handing out a request.
+    ///
+    /// A request is decomposed like [`Cx`] does, and comes with the token of its
+    /// cancellation. The reply is composed back with [`CancelToken::id`].
+    pub fn accept<P, U, R>(this: P, item: R) -> Option<(U, CancelToken<P>)>
+    where
+        P: Deref<Target = Self>,
+        R: Identifier<U>,
+    {
+        if let Some(id) = item.cancelled() {
+            this.cancel(id);
+            return None;
+        }
+        if let Some((id, n)) = item.credited() {
+            this.grant(id, n);
+            return None;
+        }
+        let (payload, id) = item.decompose();
+        Some((payload, CancelToken { set: this, id }))
+    }
+
+    /// Cancels the request `id`, returns `false` if it is out of range.
+    pub fn cancel(&self, id: Id) -> bool {
+        let Some(slot) = self.slots.get(id.idx) else {
+            return false;
+        };
+        slot.store(CANCELLED | id.live as u64, Ordering::Release);
+        // A stream awaiting credits gives up.
+        self.credits[id.idx].wake();
+        true
+    }
+
+    pub fn is_cancelled(&self, id: Id) -> bool {
+        self.slots
+            .get(id.idx)
+            .is_some_and(|slot| slot.load(Ordering::Acquire) == CANCELLED | id.live as u64)
+    }
+
+    /// Grants `n` more chunks to the stream `id`, returns `false` if it is out of range.
+    pub fn grant(&self, id: Id, n: u32) -> bool {
+        let Some(credit) = self.credits.get(id.idx) else {
+            return false;
+        };
+        let live = (id.live as u64) << 32;
+        let _ = credit
+            .grant
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+                // The credits of an earlier request are void.
+                let held = if cur & !(u32::MAX as u64) == live {
+                    cur as u32
+                } else {
+                    0
+                };
+                Some(live | held.saturating_add(n) as u64)
+            });
+        credit.wake();
+        true
+    }
+
+    /// Takes a credit of the stream `id`, returns `false` if none is left.
+    pub fn try_acquire(&self, id: Id) -> bool {
+        let Some(credit) = self.credits.get(id.idx) else {
+            return false;
+        };
+        let live = (id.live as u64) << 32;
+        credit
+            .grant
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+                (cur & !(u32::MAX as u64) == live && cur as u32 > 0).then(|| cur - 1)
+            })
+            .is_ok()
+    }
+
+    fn poll_acquire(&self, id: Id, cx: &mut Context<'_>) -> Poll<bool> {
+        let ready = || {
+            if self.try_acquire(id) {
+                Some(true)
+            } else {
+                self.is_cancelled(id).then_some(false)
+            }
+        };
+        if let Some(res) = ready() {
+            return Poll::Ready(res);
+        }
+        let Some(credit) = self.credits.get(id.idx) else {
+            return Poll::Ready(false);
+        };
+        *credit.waker.lock() = Some(cx.waker().clone());
+        // A grant may have raced with the registration.
+        match ready() {
+            Some(res) => Poll::Ready(res),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// The cancellation of a request being served, see [`CancelSet::accept`].
+///
+/// Long-running handlers poll [`CancelToken::is_cancelled`] and give up once the client did.
+#[derive(Debug)]
+pub struct CancelToken<P: Deref<Target = CancelSet>> {
+    set: P,
+    id: Id,
+}
+
+impl<P: Deref<Target = CancelSet>> CancelToken<P> {
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.set.is_cancelled(self.id)
+    }
+
+    /// Takes a credit to send a chunk of the streamed response, see [`CancelSet::grant`].
+    #[inline]
+    pub fn try_acquire(&self) -> bool {
+        self.set.try_acquire(self.id)
+    }
+
+    /// Awaits a credit to send a chunk of the streamed response, `false` once cancelled.
+    ///
+    /// Each chunk, the last one included, takes a credit. Compose the chunks followed by more
+    /// with [`Identified::compose_more`].
+    pub fn acquire(&self) -> impl Future<Output = bool> + '_ {
+        core::future::poll_fn(|cx| self.set.poll_acquire(self.id, cx))
     }
 }
 
+/// The handler of the requests accepted by [`serve`].
+pub trait Service<A> {
+    type Request;
+    type Response;
+
+    /// Handles a request, the response is sent back under the id of the request.
+    fn call(&self, request: Self::Request, alloc: &A) -> impl Future<Output = Self::Response>;
+
+    /// The number of requests handled at once.
+    #[inline]
+    fn concurrency(&self) -> usize {
+        1
+    }
+}
+
+enum Event<T, E, P> {
+    Received(Result<T, E>),
+    /// The response of the request at the slot.
+    Handled(Id, usize, P),
+}
+
+/// Polls the handled requests, returning the first ready response and its slot.
+fn poll_handled<F: Future + Unpin>(
+    handled: &mut [(Id, F)],
+    cx: &mut Context<'_>,
+) -> Poll<(Id, usize, F::Output)> {
+    for (i, (id, fut)) in handled.iter_mut().enumerate() {
+        if let Poll::Ready(response) = core::pin::Pin::new(fut).poll(cx) {
+            return Poll::Ready((*id, i, response));
+        }
+    }
+    Poll::Pending
+}
+
+/// Serves the requests of `receiver` with `service` until the client closes, returning how many
+/// responses were sent.
+///
+/// Each request is decomposed like [`Cx`] does, its response is composed back under the same id,
+/// so the header of the request travels back untouched. Up to [`Service::concurrency`] requests
+/// are handled at once, and no more are received while a response waits for room in `sender`.
+/// Cancel and credit messages are dropped, see [`CancelSet`] to observe them.
+///
+/// Each request is answered by a single response, which ends a [`StreamOp`] at once. Streamed
+/// responses need a loop of their own, sorting the received items with [`CancelSet::accept`]
+/// and sending a chunk per credit of [`CancelToken::acquire`].
+///
+/// Once `receiver` is disconnected, the requests in flight are still answered, then `sender` is
+/// closed. Once `sender` is disconnected, the unsent response and those of the requests in
+/// flight are handed to `alloc` as a [`DeadLetter`], as they are if the future is dropped while
+/// a response waits for room.
+pub async fn serve<R, S, A, Sv>(receiver: R, sender: S, alloc: A, service: Sv) -> usize
+where
+    R: super::AsyncReceiver,
+    R::Item: Identifier<Sv::Request>,
+    S: super::AsyncSender<Error = SendError<<S as super::Sender>::Item>> + QueueChannel,
+    S::Item: Identifier<Sv::Response>,
+    A: DeadLetter<Sv::Response>,
+    Sv: Service<A>,
+{
+    let limit = service.concurrency().max(1);
+    let mut handled = Vec::with_capacity(limit);
+    let mut next = core::pin::pin!(receiver.recv());
+    let mut open = true;
+    let mut n = 0;
+
+    while open || !handled.is_empty() {
+        let event = core::future::poll_fn(|cx| {
+            if let Poll::Ready((id, i, response)) = poll_handled(&mut handled, cx) {
+                return Poll::Ready(Event::Handled(id, i, response));
+            }
+            if open && handled.len() < limit {
+                return next.as_mut().poll(cx).map(Event::Received);
+            }
+            Poll::Pending
+        })
+        .await;
+
+        match event {
+            Event::Received(Ok(item)) => {
+                next.set(receiver.recv());
+                if item.cancelled().is_some() || item.credited().is_some() {
+                    continue;
+                }
+                let (request, id) = item.decompose();
+                handled.push((id, Box::pin(service.call(request, &alloc))));
+            }
+            Event::Received(Err(_)) => open = false,
+            Event::Handled(id, i, response) => {
+                drop(handled.swap_remove(i));
+                let msg = <S::Item as Identifier<Sv::Response>>::compose(response, id);
+                let unsent = |msg| alloc.dispose(Identifier::<Sv::Response>::decompose(msg).0);
+                if let Err(SendError(msg)) = sender.send_or_else(msg, unsent).await {
+                    // Nobody waits for the responses anymore.
+                    unsent(msg);
+                    for (_, response) in handled.drain(..) {
+                        alloc.dispose(response.await);
+                    }
+                    break;
+                }
+                n += 1;
+            }
+        }
+    }
+    sender.close();
+    n
+}
+
+/// The chains received by a server, see [`ChainBuilder`].
+///
+/// The links of a chain may arrive interleaved with other requests, each one is found back by
+/// the id its previous link carries. A chain is only found back whole by a single receiver:
+/// serve the chains of a queue from one receiver, not from the clones of an MPMC one which
+/// would split them.
+///
+/// A chain whose submission fails midway never completes. The ops of its links sent are
+/// cancelled as they drop, and their cancel messages, which follow the links, are meant for
+/// [`Links::abort`]. The chains still missing links once the client is gone are handed back
+/// by [`Links::drain`].
+#[derive(Debug)]
+pub struct Links<T> {
+    /// The chains missing links, with the id of the next one.
+    partial: Vec<(Vec<(T, Id)>, Id)>,
+}
+
+impl<T> Default for Links<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Links<T> {
+    pub const fn new() -> Self {
+        Self {
+            partial: Vec::new(),
+        }
+    }
+
+    /// Takes a received request, returning its chain once the last link arrives.
+    ///
+    /// A request linked to nothing and following no link is a chain of its own.
+    pub fn push<R: Identifier<T>>(&mut self, item: R) -> Option<Vec<(T, Id)>> {
+        let next = item.linked();
+        let (request, id) = item.decompose();
+        let mut chain = match self.partial.iter().position(|(_, awaited)| *awaited == id) {
+            Some(i) => self.partial.swap_remove(i).0,
+            None => Vec::new(),
+        };
+        chain.push((request, id));
+        match next {
+            Some(next) => {
+                self.partial.push((chain, next));
+                None
+            }
+            None => Some(chain),
+        }
+    }
+
+    /// Drops the chain missing links which holds the link `id`, e.g. on its cancel message,
+    /// handing back its links to be freed.
+    pub fn abort(&mut self, id: Id) -> Option<Vec<(T, Id)>> {
+        let i = self
+            .partial
+            .iter()
+            .position(|(chain, _)| chain.iter().any(|(_, link)| *link == id))?;
+        Some(self.partial.swap_remove(i).0)
+    }
+
+    /// Hands back the links of every chain missing links, e.g. once the client is gone.
+    pub fn drain(&mut self) -> impl Iterator<Item = (T, Id)> + '_ {
+        self.partial.drain(..).flat_map(|(chain, _)| chain)
+    }
+
+    /// Returns `true` if no chain misses a link.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.partial.is_empty()
+    }
+}
+
+/// Serves `chain` in order with `f`, answering each link once the previous one succeeded.
+///
+/// The first link failing with `Err` breaks the chain: its response is sent all the same, and the
+/// links after it are answered with a cancel message, failing their ops with
+/// [`OpError::Cancelled`]. The requests of the skipped links are handed back to be freed.
+///
+/// Without a cancel message in the envelope, see [`Envelope::control`], a skipped link is
+/// answered with the response `skip` makes of its request instead.
+///
+/// [`Envelope::control`]: crate::msg::Envelope::control
+pub async fn serve_chain<S, T, Resp>(
+    sender: &S,
+    chain: Vec<(T, Id)>,
+    mut f: impl AsyncFnMut(T) -> Result<Resp, Resp>,
+    mut skip: impl FnMut(T) -> Resp,
+) -> Result<Vec<T>, S::Error>
+where
+    S: super::AsyncSender,
+    S::Item: Identifier<Resp>,
+{
+    let mut links = chain.into_iter();
+    for (request, id) in links.by_ref() {
+        let result = f(request).await;
+        let broken = result.is_err();
+        let (Ok(response) | Err(response)) = result;
+        let msg = <S::Item as Identifier<Resp>>::compose(response, id);
+        sender.send(msg).await?;
+        if broken {
+            break;
+        }
+    }
+    let mut skipped = Vec::new();
+    for (request, id) in links {
+        match <S::Item as Identifier<Resp>>::cancel(id) {
+            Some(msg) => {
+                sender.send(msg).await?;
+                skipped.push(request);
+            }
+            None => {
+                let msg = <S::Item as Identifier<Resp>>::compose(skip(request), id);
+                sender.send(msg).await?;
+            }
+        }
+    }
+    Ok(skipped)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::channel::driver::CachePool;
@@ -695,7 +2282,7 @@ mod tests {
 
         tracing_init();
 
-        let pool = Arc::new(CachePool::<u32, N>::new());
+        let pool = Arc::new(CachePool::<u32>::new(N));
         let bar = Arc::new(Barrier::new(2 * N));
         thread::scope(|s| {
             let mut ids = Vec::with_capacity(N);
@@ -717,7 +2304,7 @@ mod tests {
                     let tid = thread::current().id();
                     tracing::debug!("{:?} complete id: {:?}", tid, id);
                     thread::sleep(std::time::Duration::from_micros(fastrand::u64(50..300)));
-                    pool.complete(id, fastrand::u32(0..100));
+                    pool.complete_or(id, fastrand::u32(0..100), true, drop);
                 }));
             }
 
@@ -752,4 +2339,26 @@ mod tests {
         let (_, id) = pool.probe().expect("should allocate");
         assert_ne!(id.live, 0)
     }
+
+    #[test]
+    fn pool_capacity() {
+        for cap in [1, 3, 5] {
+            let pool = CachePool::<u32>::new(cap);
+            assert_eq!(pool.capacity(), cap);
+
+            let mut ops = (0..cap)
+                .map(|_| pool.probe().expect("should allocate").0)
+                .collect::<Vec<_>>();
+            assert_eq!(pool.len(), cap);
+            assert!(pool.probe().is_none());
+
+            // A released entry is claimed again, and only that one.
+            ops.pop();
+            let (op, _) = pool.probe().expect("should allocate");
+            assert!(pool.probe().is_none());
+            drop(op);
+            drop(ops);
+            assert!(pool.probe().is_some());
+        }
+    }
 }
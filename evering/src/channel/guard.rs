@@ -0,0 +1,169 @@
+//! In-place access to the slots of a [`Queue`], sparing the moves of large items.
+//!
+//! A slot is claimed by the very [`QueueOps::claim_tail`] or [`QueueOps::claim_head`] of
+//! [`QueueOps::push`] and [`QueueOps::pop`], but its stamp is only updated once the guard is done
+//! with it. Until then the peers reaching that slot find the queue full, or empty.
+//!
+//! In MPMC mode a reservation cannot be undone once later slots are claimed, so a released one is
+//! marked abandoned and skipped by consumers. In SPSC mode nothing is published before commit.
+
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use core::sync::atomic::Ordering;
+
+use super::resize::{Pass, Side};
+use super::{Mode, Queue, QueueOps, slot_at, spsc};
+
+/// A slot reserved at the tail of a queue, see [`QueueTx::reserve`](super::QueueTx::reserve).
+///
+/// The item is constructed in place through `DerefMut` and published by
+/// [`SlotGuard::commit`]. Dropping the guard releases the slot without publishing anything.
+pub struct SlotGuard<'a, Q: Queue> {
+    q: &'a Q,
+    pos: usize,
+    pass: Pass<'a>,
+}
+
+impl<'a, Q: Queue> SlotGuard<'a, Q> {
+    #[inline]
+    pub(super) fn reserve(q: &'a Q) -> Option<Self> {
+        let pass = q.header().enter(Side::Tx);
+        let claim = match q.header().mode {
+            Mode::Spsc => spsc::reserve(q),
+            Mode::Mpmc => q.claim_tail(),
+        };
+        let Some(pos) = claim else {
+            q.header().count_push(0);
+            return None;
+        };
+        Some(Self { q, pos, pass })
+    }
+
+    /// Publishes the item to the receivers.
+    ///
+    /// # Safety
+    ///
+    /// The item must be fully initialized.
+    pub unsafe fn commit(self) {
+        let this = ManuallyDrop::new(self);
+        let header = this.q.header();
+        match header.mode {
+            Mode::Spsc => spsc::commit(this.q, this.pos),
+            Mode::Mpmc => slot_at(this.q, this.pos)
+                .stamp
+                .store(this.pos + 1, Ordering::Release),
+        }
+        header.count_push(1);
+        header.readable.notify();
+        // Safety: the guard is forgotten, the pass is dropped once.
+        drop(unsafe { ptr::read(&this.pass) });
+    }
+
+    /// Initializes the item with `value` and publishes it.
+    #[inline]
+    pub fn commit_with(mut self, value: Q::Item) {
+        MaybeUninit::write(&mut self, value);
+        unsafe { self.commit() }
+    }
+}
+
+impl<Q: Queue> Deref for SlotGuard<'_, Q> {
+    type Target = MaybeUninit<Q::Item>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*slot_at(self.q, self.pos).value.get() }
+    }
+}
+
+impl<Q: Queue> DerefMut for SlotGuard<'_, Q> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { &mut *slot_at(self.q, self.pos).value.get() }
+    }
+}
+
+impl<Q: Queue> Drop for SlotGuard<'_, Q> {
+    fn drop(&mut self) {
+        let header = self.q.header();
+        if header.mode == Mode::Mpmc {
+            let slot = slot_at(self.q, self.pos);
+            slot.abandoned.store(true, Ordering::Relaxed);
+            slot.stamp.store(self.pos + 1, Ordering::Release);
+            // The items sent behind the reservation are reachable again.
+            header.readable.notify();
+        }
+    }
+}
+
+/// The item at the head of a queue, see [`QueueRx::peek_front`](super::QueueRx::peek_front).
+///
+/// The item is read in place, dropping the guard drops it and acknowledges the slot to the
+/// senders.
+pub struct ReadGuard<'a, Q: Queue> {
+    q: &'a Q,
+    pos: usize,
+    pass: Pass<'a>,
+}
+
+impl<'a, Q: Queue> ReadGuard<'a, Q> {
+    #[inline]
+    pub(super) fn front(q: &'a Q) -> Option<Self> {
+        let pass = q.header().enter(Side::Rx);
+        let claim = match q.header().mode {
+            Mode::Spsc => spsc::front(q),
+            Mode::Mpmc => q.claim_head(),
+        };
+        let Some(pos) = claim else {
+            q.header().count_pop(0);
+            return None;
+        };
+        Some(Self { q, pos, pass })
+    }
+
+    /// Moves the item out and acknowledges the slot.
+    pub fn take(self) -> Q::Item {
+        let this = ManuallyDrop::new(self);
+        let item = unsafe { slot_at(this.q, this.pos).value.get().read().assume_init() };
+        this.ack();
+        // Safety: the guard is forgotten, the pass is dropped once.
+        drop(unsafe { ptr::read(&this.pass) });
+        item
+    }
+
+    fn ack(&self) {
+        let header = self.q.header();
+        match header.mode {
+            Mode::Spsc => spsc::ack(self.q, self.pos),
+            Mode::Mpmc => slot_at(self.q, self.pos)
+                .stamp
+                .store(self.pos.wrapping_add(header.one_lap()), Ordering::Release),
+        }
+        header.count_pop(1);
+        header.writable.notify();
+    }
+}
+
+impl<Q: Queue> Deref for ReadGuard<'_, Q> {
+    type Target = Q::Item;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { (*slot_at(self.q, self.pos).value.get()).assume_init_ref() }
+    }
+}
+
+impl<Q: Queue> DerefMut for ReadGuard<'_, Q> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { (*slot_at(self.q, self.pos).value.get()).assume_init_mut() }
+    }
+}
+
+impl<Q: Queue> Drop for ReadGuard<'_, Q> {
+    fn drop(&mut self) {
+        unsafe { (*slot_at(self.q, self.pos).value.get()).assume_init_drop() };
+        self.ack();
+    }
+}
@@ -1,11 +1,29 @@
-use core::{
-    cell::UnsafeCell,
-    mem::MaybeUninit,
-    panic::{RefUnwindSafe, UnwindSafe},
-    sync::atomic::AtomicUsize,
-};
+//! An in-process channel on the heap, for tests and single-process deployments.
+//!
+//! The endpoints are the very [`QueueTx`] and [`QueueRx`] of the shared memory queues, so the
+//! drivers and the RPC code built on them run unchanged, without mapping any region.
 
-use crate::channel::{Header, Slot};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::channel::{Endpoint, Header, Mode, QueueRx, QueueTx, Slot};
+
+pub type Sender<T> = QueueTx<LocalQueue<T>>;
+pub type Receiver<T> = QueueRx<LocalQueue<T>>;
+
+/// Creates a channel of `cap` slots, shared by any number of senders and receivers.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    let q = LocalQueue(Arc::new(Queue::new(cap)));
+    // The sides of an MPMC queue take any number of endpoints.
+    let tx = q.clone().sender().expect("MPMC queue");
+    let rx = q.receiver().expect("MPMC queue");
+    (tx, rx)
+}
 
 struct Queue<T> {
     h: Header,
@@ -43,18 +61,14 @@ impl<T> Drop for Queue<T> {
 
 impl<T> Queue<T> {
     fn new(cap: usize) -> Self {
-        let h = Header::new(cap);
+        Self::with_mode(cap, Mode::Mpmc)
+    }
+
+    fn with_mode(cap: usize, mode: Mode) -> Self {
+        let h = Header::new(cap, mode);
         // Allocate a buffer of `cap` slots initialized
         // with stamps.
-        let buf: Box<[Slot<T>]> = (0..cap)
-            .map(|i| {
-                // Set the stamp to `{ lap: 0, index: i }`.
-                Slot {
-                    stamp: AtomicUsize::new(i),
-                    value: UnsafeCell::new(MaybeUninit::uninit()),
-                }
-            })
-            .collect();
+        let buf: Box<[Slot<T>]> = (0..cap).map(Slot::vacant).collect();
 
         Self { h, buf }
     }
@@ -66,6 +80,43 @@ impl<T> Queue<T> {
     }
 }
 
+/// A heap queue held by the endpoints of a [`bounded`] channel, freed with the last of them.
+pub struct LocalQueue<T>(Arc<Queue<T>>);
+
+impl<T> Clone for LocalQueue<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> PartialEq for LocalQueue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<T> core::fmt::Debug for LocalQueue<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.pad("LocalQueue { .. }")
+    }
+}
+
+impl<T> super::Queue for LocalQueue<T> {
+    type Item = T;
+
+    #[inline]
+    fn header(&self) -> &Header {
+        &self.0.h
+    }
+
+    #[inline]
+    fn buf(&self) -> &[Slot<Self::Item>] {
+        &self.0.buf
+    }
+}
+
+impl<T> Endpoint for LocalQueue<T> {}
+
 impl<T> super::Queue for QueueHandle<'_, T> {
     type Item = T;
 
@@ -82,10 +133,11 @@ impl<T> super::Queue for QueueHandle<'_, T> {
 
 #[cfg(test)]
 mod tests {
-    use super::super::QueueOps;
-    use super::Queue;
+    use super::super::{AttachError, Endpoint, Mode, QueueOps, ReadGuard, SlotGuard, attach_pair};
+    use super::{LocalQueue, Queue};
+    use core::mem::MaybeUninit;
     use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::thread::scope;
+    use std::thread::{scope, yield_now};
 
     #[test]
     fn smoke() {
@@ -114,6 +166,396 @@ mod tests {
         let _ = Queue::<i32>::new(0);
     }
 
+    #[test]
+    fn batch() {
+        let q = Queue::new(4);
+        let handle = q.handle();
+
+        let mut iter = 0..6;
+        assert_eq!(handle.push_batch(&mut iter), 4);
+        assert_eq!(iter.clone().collect::<Vec<_>>(), [4, 5]);
+
+        let mut out = Vec::new();
+        assert_eq!(handle.pop_batch(&mut out, 3), 3);
+        assert_eq!(out, [0, 1, 2]);
+
+        // The run wraps around the end of lap.
+        assert_eq!(handle.push_batch(&mut iter), 2);
+        assert_eq!(handle.push_batch(&mut (6..10)), 1);
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 4);
+        assert_eq!(out, [0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 0);
+    }
+
+    #[test]
+    fn batch_unsized() {
+        let q = Queue::new(4);
+        let handle = q.handle();
+
+        // No lower bound, a single slot is claimed before each element is taken.
+        let mut iter = (0..12).filter(|i| i % 2 == 0);
+        assert_eq!(handle.push_batch(&mut iter), 4);
+        assert_eq!(iter.next(), Some(8));
+
+        // A lower bound broken by the iterator abandons the unused slots, which are skipped.
+        let mut out = Vec::new();
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 4);
+        assert_eq!(handle.push_batch(&mut Short(2)), 2);
+        assert_eq!(handle.push(7), Err(7));
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 2);
+        assert_eq!(handle.push(7), Ok(()));
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 1);
+        assert_eq!(out, [0, 2, 4, 6, 0, 1, 7]);
+
+        // The last slot claimed by an iterator without an upper bound may be abandoned too.
+        assert_eq!(handle.push_batch(&mut (0..3).filter(|_| true)), 3);
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 3);
+        assert!(handle.is_empty());
+    }
+
+    /// Yields `n` elements while claiming twice as many.
+    struct Short(usize);
+
+    impl Iterator for Short {
+        type Item = usize;
+
+        fn next(&mut self) -> Option<usize> {
+            self.0 = self.0.checked_sub(1)?;
+            Some(1 - self.0)
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.0 * 2, None)
+        }
+    }
+
+    #[test]
+    fn mpmc_batch() {
+        #[cfg(miri)]
+        const COUNT: usize = 50;
+        #[cfg(not(miri))]
+        const COUNT: usize = 25_000;
+        const THREADS: usize = 4;
+        const BATCH: usize = 5;
+
+        let q = Queue::<usize>::new(7);
+        let v = (0..COUNT).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>();
+        let received = AtomicUsize::new(0);
+
+        scope(|scope| {
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    let mut out = Vec::with_capacity(BATCH);
+                    while received.load(Ordering::SeqCst) < COUNT * THREADS {
+                        let n = q.handle().pop_batch(&mut out, BATCH);
+                        if n == 0 {
+                            yield_now();
+                        }
+                        for i in out.drain(..) {
+                            v[i].fetch_add(1, Ordering::SeqCst);
+                        }
+                        received.fetch_add(n, Ordering::SeqCst);
+                    }
+                });
+            }
+
+            for _ in 0..THREADS {
+                scope.spawn(|| {
+                    let mut iter = 0..COUNT;
+                    while !iter.is_empty() {
+                        if q.handle().push_batch(&mut iter) == 0 {
+                            yield_now();
+                        }
+                    }
+                });
+            }
+        });
+
+        for c in v {
+            assert_eq!(c.load(Ordering::SeqCst), THREADS);
+        }
+    }
+
+    #[test]
+    fn guard() {
+        for mode in [Mode::Mpmc, Mode::Spsc] {
+            let q = Queue::<Box<i32>>::with_mode(2, mode);
+            let handle = q.handle();
+
+            let mut slot = SlotGuard::reserve(&handle).unwrap();
+            MaybeUninit::write(&mut slot, Box::new(7));
+            unsafe { slot.commit() };
+            SlotGuard::reserve(&handle)
+                .unwrap()
+                .commit_with(Box::new(8));
+            assert!(SlotGuard::reserve(&handle).is_none());
+
+            let mut front = ReadGuard::front(&handle).unwrap();
+            assert_eq!(**front, 7);
+            **front = 9;
+            drop(front);
+            assert_eq!(*ReadGuard::front(&handle).unwrap().take(), 8);
+            assert!(ReadGuard::front(&handle).is_none());
+
+            // A released reservation is never received.
+            drop(SlotGuard::reserve(&handle).unwrap());
+            handle.push(Box::new(10)).unwrap();
+            if mode == Mode::Mpmc {
+                assert_eq!(handle.force_push(Box::new(11)), None);
+            }
+            assert_eq!(handle.pop().as_deref(), Some(&10));
+            if mode == Mode::Mpmc {
+                assert_eq!(handle.pop().as_deref(), Some(&11));
+            }
+            assert!(handle.pop().is_none());
+
+            // Leftovers and abandoned slots are dropped with the queue.
+            drop(SlotGuard::reserve(&handle).unwrap());
+            handle.push(Box::new(12)).unwrap();
+        }
+    }
+
+    #[test]
+    fn guard_pending() {
+        for mode in [Mode::Mpmc, Mode::Spsc] {
+            let q = Queue::<i32>::with_mode(2, mode);
+            let handle = q.handle();
+
+            // An uncommitted reservation reads as empty.
+            let slot = SlotGuard::reserve(&handle).unwrap();
+            assert!(handle.pop().is_none());
+            assert!(ReadGuard::front(&handle).is_none());
+            slot.commit_with(1);
+            handle.push(2).unwrap();
+
+            // An unacknowledged read reads as full.
+            let front = ReadGuard::front(&handle).unwrap();
+            assert_eq!(handle.push(3), Err(3));
+            assert!(SlotGuard::reserve(&handle).is_none());
+            assert_eq!(front.take(), 1);
+            handle.push(3).unwrap();
+            assert_eq!(handle.pop(), Some(2));
+            assert_eq!(handle.pop(), Some(3));
+
+            // An open reservation holds back the items sent behind it, its release wakes the
+            // parked receivers.
+            if mode == Mode::Mpmc {
+                let slot = SlotGuard::reserve(&handle).unwrap();
+                handle.push(4).unwrap();
+                assert!(handle.pop().is_none());
+                let key = handle.h.readable.register();
+                drop(slot);
+                assert_ne!(handle.h.readable.register(), key);
+                handle.h.readable.unregister();
+                handle.h.readable.unregister();
+                assert_eq!(handle.pop(), Some(4));
+            }
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats() {
+        use super::super::{Queue as _, QueueStats};
+
+        for mode in [Mode::Mpmc, Mode::Spsc] {
+            let q = Queue::with_mode(2, mode);
+            let handle = q.handle();
+
+            handle.push(1).unwrap();
+            handle.push(2).unwrap();
+            assert_eq!(handle.push(3), Err(3));
+            assert_eq!(handle.pop(), Some(1));
+            assert_eq!(handle.pop(), Some(2));
+            assert_eq!(handle.pop(), None);
+
+            let mut out = Vec::new();
+            assert_eq!(handle.push_batch(&mut (3..6)), 2);
+            assert_eq!(handle.pop_batch(&mut out, 1), 1);
+            assert_eq!(handle.pop_batch(&mut out, 0), 0);
+            assert_eq!(handle.pop_batch(&mut out, usize::MAX), 1);
+
+            assert_eq!(
+                handle.header().stats(),
+                QueueStats {
+                    pushed: 4,
+                    popped: 4,
+                    full: 1,
+                    empty: 1,
+                    high_water: 2,
+                }
+            );
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn stats_waiting() {
+        use super::super::QueueChannel;
+        use std::time::Duration;
+
+        // A wait counts once, however many times it retries.
+        let (tx, rx) = super::bounded(1);
+        assert!(rx.recv_timeout(Duration::from_millis(20)).is_err());
+        assert_eq!(rx.stats().empty, 1);
+        tx.try_send(1).unwrap();
+        assert!(tx.send_timeout(2, Duration::from_millis(20)).is_err());
+        assert_eq!(tx.stats().full, 1);
+        assert_eq!(rx.recv_blocking(), Ok(1));
+        assert_eq!(rx.stats().empty, 1);
+    }
+
+    #[test]
+    fn spsc_smoke() {
+        let q = Queue::with_mode(2, Mode::Spsc);
+        let handle = q.handle();
+
+        handle.push(7).unwrap();
+        handle.push(8).unwrap();
+        assert_eq!(handle.push(9), Err(9));
+        assert_eq!(handle.force_push(9), Some(9));
+        assert_eq!(handle.len(), 2);
+        assert_eq!(handle.pop(), Some(7));
+
+        let mut out = Vec::new();
+        assert_eq!(handle.push_batch(&mut (9..12)), 1);
+        assert_eq!(handle.pop_batch(&mut out, usize::MAX), 2);
+        assert_eq!(out, [8, 9]);
+        assert!(handle.pop().is_none());
+
+        // Leftovers are dropped with the queue.
+        let q = Queue::with_mode(3, Mode::Spsc);
+        q.handle().push(Box::new(1)).unwrap();
+    }
+
+    #[test]
+    fn spsc_batch() {
+        #[cfg(miri)]
+        const COUNT: usize = 50;
+        #[cfg(not(miri))]
+        const COUNT: usize = 25_000;
+        const BATCH: usize = 5;
+
+        let q = Queue::<usize>::with_mode(7, Mode::Spsc);
+
+        scope(|scope| {
+            scope.spawn(|| {
+                let mut out = Vec::with_capacity(BATCH);
+                let mut next = 0;
+                while next < COUNT {
+                    if q.handle().pop_batch(&mut out, BATCH) == 0 {
+                        yield_now();
+                    }
+                    for i in out.drain(..) {
+                        assert_eq!(i, next);
+                        next += 1;
+                    }
+                }
+            });
+
+            scope.spawn(|| {
+                let mut iter = 0..COUNT;
+                while !iter.is_empty() {
+                    if q.handle().push_batch(&mut iter) == 0 {
+                        yield_now();
+                    }
+                }
+            });
+        });
+
+        assert!(q.handle().is_empty());
+    }
+
+    #[test]
+    fn spsc_exclusive() {
+        use std::panic::catch_unwind;
+        use std::sync::Arc;
+
+        let q = LocalQueue(Arc::new(Queue::<i32>::with_mode(2, Mode::Spsc)));
+        let tx = q.clone().sender().unwrap();
+        let _rx = q.clone().receiver().unwrap();
+        assert!(catch_unwind(|| tx.clone()).is_err());
+        assert_eq!(q.clone().receiver().err(), Some(AttachError));
+        assert_eq!(q.0.h.senders(), 1);
+        assert_eq!(q.0.h.receivers(), 1);
+
+        // A pair is attached as a whole, the sender is not left behind by the receiver.
+        drop(tx);
+        assert_eq!(attach_pair(q.clone(), q.clone()).err(), Some(AttachError));
+        assert_eq!(q.0.h.senders(), 0);
+        assert!(q.0.h.is_close());
+
+        // The side is free again once its endpoint is gone, and the queue reopened.
+        let _tx = q.clone().sender().unwrap();
+        assert_eq!(q.0.h.senders(), 1);
+        assert!(!q.0.h.is_close());
+    }
+
+    #[cfg(feature = "unix")]
+    #[test]
+    fn probe() {
+        let q = Queue::<i32>::new(1);
+        q.h.attach_sender().unwrap();
+        q.h.attach_receiver().unwrap();
+        assert!(!q.h.probe());
+
+        // A reaped child is a gone process.
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        // The endpoints of the child are detached, those of this process stay.
+        q.h.receivers.count.fetch_add(2, Ordering::Relaxed);
+        q.h.receivers.track(pid);
+        q.h.receivers.track(pid);
+        assert!(!q.h.probe());
+        assert_eq!(q.h.receivers(), 1);
+
+        // So is the last sender, which closes the queue.
+        assert!(q.h.senders.detach());
+        q.h.senders.count.fetch_add(1, Ordering::Relaxed);
+        q.h.senders.track(pid);
+        assert!(q.h.probe());
+        assert_eq!(q.h.senders(), 0);
+
+        // A new sender reopens the queue for the remaining receiver.
+        q.h.attach_sender().unwrap();
+        assert!(!q.h.probe());
+
+        // Without receivers, a new sender leaves the queue closed.
+        q.h.detach_receiver();
+        assert!(q.h.is_close());
+        q.h.attach_sender().unwrap();
+        assert!(q.h.is_close());
+    }
+
+    #[cfg(feature = "unix")]
+    #[test]
+    fn probe_parked() {
+        use crate::channel::{QueueChannel, RecvError};
+
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        // The last sender is held by a gone process, which never notifies.
+        let gone = || {
+            let (tx, rx) = super::bounded::<i32>(1);
+            let senders = &rx.handle().0.h.senders;
+            senders.count.fetch_add(1, Ordering::Relaxed);
+            senders.track(pid);
+            drop(tx);
+            assert!(!rx.is_close());
+            rx
+        };
+
+        assert_eq!(gone().recv_blocking(), Err(RecvError));
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(rt.block_on(gone().recv()), Err(RecvError));
+    }
+
     #[test]
     fn mpmc_ring_buffer() {
         #[cfg(miri)]
@@ -160,4 +602,56 @@ mod tests {
             assert_eq!(c.load(Ordering::SeqCst), THREADS);
         }
     }
+
+    #[test]
+    fn channel() {
+        use super::super::{RecvError, TryRecvError, TrySendError};
+        use super::bounded;
+        use std::sync::Arc;
+
+        let (tx, rx) = bounded(2);
+        let item = Arc::new(7);
+        tx.try_send(item.clone()).unwrap();
+        tx.clone().try_send(item.clone()).unwrap();
+        assert!(matches!(
+            tx.try_send(item.clone()),
+            Err(TrySendError::Full(_))
+        ));
+
+        scope(|scope| {
+            let rx = rx.clone();
+            scope.spawn(move || {
+                for _ in 0..3 {
+                    assert_eq!(*rx.recv_blocking().unwrap(), 7);
+                }
+                assert_eq!(rx.recv_blocking(), Err(RecvError));
+            });
+            tx.send_blocking(item.clone()).unwrap();
+            drop(tx);
+        });
+        assert!(matches!(rx.try_recv(), Err(TryRecvError::Disconnected)));
+
+        // The pending items are dropped with the last endpoint.
+        let (tx, rx) = bounded(2);
+        tx.try_send(item.clone()).unwrap();
+        drop((tx, rx));
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[tokio::test]
+    async fn driver() {
+        use super::super::driver::{CachePoolHandle, Completer, Discard, Submitter, TryCompState};
+        use super::bounded;
+
+        let (tx, server_rx) = bounded(4);
+        let (server_tx, rx) = bounded(4);
+        let (sx, cx) = CachePoolHandle::<u32>::new(4).bind(tx, rx, Discard);
+
+        let op = sx.try_submit(7).unwrap();
+        let (req, id) = server_rx.recv().await.unwrap();
+        server_tx.try_send((req * 2, id)).unwrap();
+        // Completed before the op is first polled.
+        assert_eq!(cx.complete().unwrap(), TryCompState::Prefilled);
+        assert_eq!(op.await, Ok(14));
+    }
 }
@@ -0,0 +1,543 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// A futex word shared by both ends of a queue direction.
+///
+/// A waiter advertises itself in `waiters` before sleeping on `seq`, so the notifier on the hot
+/// path pays a single load when nobody is parked. Both sides access `waiters` with `SeqCst`, and
+/// the queue operations they race with are `SeqCst` as well: either the notifier observes the
+/// waiter, or the waiter observes the new queue state on its re-check.
+pub struct Notify {
+    seq: AtomicU32,
+    waiters: AtomicU32,
+    /// The consumers awaiting a doorbell rather than the futex, see [`BellRx`].
+    ///
+    /// [`BellRx`]: super::doorbell::BellRx
+    bells: AtomicU32,
+}
+
+impl Notify {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            waiters: AtomicU32::new(0),
+            bells: AtomicU32::new(0),
+        }
+    }
+
+    /// Wakes every parked waiter, if any advertised itself.
+    #[inline]
+    pub fn notify(&self) {
+        if self.waiters.load(Ordering::SeqCst) != 0 {
+            self.notify_all();
+        }
+    }
+
+    /// Wakes every parked waiter unconditionally.
+    pub fn notify_all(&self) {
+        self.seq.fetch_add(1, Ordering::SeqCst);
+        sys::wake(&self.seq, u32::MAX);
+    }
+
+    /// Returns `true` if some waiter is parked or about to park.
+    #[inline]
+    pub fn is_waiting(&self) -> bool {
+        self.waiters.load(Ordering::SeqCst) != 0
+    }
+
+    /// Advertises a waiter and returns the key to sleep on.
+    ///
+    /// The caller must re-check its condition after registering and before sleeping.
+    #[inline]
+    pub(super) fn register(&self) -> u32 {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub(super) fn unregister(&self) {
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if some consumer awaits a doorbell.
+    #[inline]
+    pub fn is_listening(&self) -> bool {
+        self.bells.load(Ordering::SeqCst) != 0
+    }
+
+    /// Advertises a consumer awaiting a doorbell, which the futex never wakes.
+    ///
+    /// The caller must re-check its condition after listening and before awaiting.
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub(super) fn listen(&self) {
+        self.bells.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub(super) fn unlisten(&self) {
+        self.bells.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "std")]
+impl Notify {
+    /// Polls `f` until it yields, blocking the thread on the futex in between.
+    ///
+    /// Returns `None` once `deadline` passes.
+    #[inline]
+    pub(super) fn block_on<R>(
+        &self,
+        deadline: Option<std::time::Instant>,
+        idle: impl FnMut(),
+        f: impl FnMut() -> Option<R>,
+    ) -> Option<R> {
+        block_on_any(&[self], deadline, idle, f)
+    }
+}
+
+/// Polls `f` until it yields, blocking the thread on all `notifies` at once in between.
+///
+/// Calls `idle` before retrying once the thread has slept for [`PROBE`] in a row, so that the
+/// caller notices a peer gone without notifying. Returns `None` once `deadline` passes.
+#[cfg(feature = "std")]
+pub(super) fn block_on_any<R>(
+    notifies: &[&Notify],
+    deadline: Option<std::time::Instant>,
+    mut idle: impl FnMut(),
+    mut f: impl FnMut() -> Option<R>,
+) -> Option<R> {
+    let backoff = crossbeam_utils::Backoff::new();
+    while !backoff.is_completed() {
+        if let Some(r) = f() {
+            return Some(r);
+        }
+        backoff.snooze();
+    }
+
+    let mut words = alloc::vec::Vec::with_capacity(notifies.len());
+    let mut probed = std::time::Instant::now();
+    loop {
+        words.clear();
+        for notify in notifies {
+            words.push((&notify.seq as *const AtomicU32, notify.register()));
+        }
+        let r = f();
+        let timeout = deadline.map(|d| d.saturating_duration_since(std::time::Instant::now()));
+        if r.is_none() && timeout.is_none_or(|t| !t.is_zero()) {
+            let slice = Some(timeout.map_or(PROBE, |t| t.min(PROBE)));
+            match words[..] {
+                [(word, key)] => {
+                    sys::wait(word, key, slice);
+                }
+                _ => {
+                    sys::wait_any(&words, slice);
+                }
+            }
+        }
+        for notify in notifies {
+            notify.unregister();
+        }
+
+        if r.is_some() {
+            return r;
+        }
+        if timeout.is_some_and(|t| t.is_zero()) {
+            // One last try, the peer may have raced the deadline.
+            return f();
+        }
+        if probed.elapsed() >= PROBE {
+            idle();
+            probed = std::time::Instant::now();
+        }
+    }
+}
+
+/// The period after which a parked waiter probes its peers, see [`Park`].
+#[cfg(feature = "std")]
+const PROBE: core::time::Duration = core::time::Duration::from_millis(100);
+
+/// The period of the waits degraded to polling.
+#[cfg(feature = "std")]
+const TICK: core::time::Duration = core::time::Duration::from_micros(50);
+
+/// Polls the words until one no longer holds its expected value, where the kernel cannot wait
+/// on them.
+#[cfg(feature = "std")]
+fn poll_any(words: &[(*const AtomicU32, u32)], timeout: Option<core::time::Duration>) -> bool {
+    let deadline = timeout.map(|t| std::time::Instant::now() + t);
+    loop {
+        // Safety: unlike the kernel this reads the words, the caller keeps them mapped.
+        if words
+            .iter()
+            .any(|&(word, expected)| unsafe { (*word).load(Ordering::Acquire) } != expected)
+        {
+            return true;
+        }
+        if deadline.is_some_and(|d| std::time::Instant::now() >= d) {
+            return false;
+        }
+        std::thread::sleep(TICK);
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::fmt::Debug for Notify {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Notify")
+            .field("seq", &self.seq.load(Ordering::Relaxed))
+            .field("waiters", &self.waiters.load(Ordering::Relaxed))
+            .field("bells", &self.bells.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+#[cfg(all(feature = "unix", target_os = "linux"))]
+mod sys {
+    use core::sync::atomic::AtomicU32;
+    use core::time::Duration;
+
+    use nix::errno::Errno;
+
+    pub use crate::os::unix::{futex_wait as wait, futex_wake as wake};
+
+    pub fn wait_any(words: &[(*const AtomicU32, u32)], timeout: Option<Duration>) -> bool {
+        match crate::os::unix::futex_waitv(words, timeout) {
+            Ok(woken) => woken.is_some(),
+            // A word changed before sleeping, or a signal interrupted the wait.
+            Err(Errno::EAGAIN | Errno::EINTR) => true,
+            // Too many words, or a kernel without `futex_waitv`.
+            Err(_) => super::poll_any(words, timeout),
+        }
+    }
+
+    /// Blocks until one of `words` leaves its expected value, without ever reading them.
+    ///
+    /// Returns the index of the word if the kernel tells it.
+    #[cfg(feature = "std")]
+    pub fn wait_index(
+        words: &[(*const AtomicU32, u32)],
+        timeout: Option<Duration>,
+    ) -> Option<usize> {
+        match crate::os::unix::futex_waitv(words, timeout) {
+            Ok(woken) => woken,
+            Err(Errno::EAGAIN | Errno::EINTR) => None,
+            // Too many words, an unmapped one, or a kernel without `futex_waitv`.
+            Err(_) => {
+                std::thread::sleep(super::TICK);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(all(feature = "unix", target_os = "linux")))]
+mod sys {
+    use core::sync::atomic::AtomicU32;
+    use core::time::Duration;
+
+    /// Without a futex the waiter degrades to polling the word.
+    #[cfg(feature = "std")]
+    pub fn wait(word: *const AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+        super::poll_any(&[(word, expected)], timeout)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn wait_any(words: &[(*const AtomicU32, u32)], timeout: Option<Duration>) -> bool {
+        super::poll_any(words, timeout)
+    }
+
+    /// Without a futex the reactor degrades to waking every waiter at each tick.
+    #[cfg(feature = "std")]
+    pub fn wait_index(
+        _words: &[(*const AtomicU32, u32)],
+        _timeout: Option<Duration>,
+    ) -> Option<usize> {
+        std::thread::sleep(super::TICK);
+        None
+    }
+
+    pub fn wake(_word: *const AtomicU32, _count: u32) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "std")]
+pub use self::park::Park;
+
+#[cfg(feature = "std")]
+mod park {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use core::task::{Context, Poll, Waker};
+
+    use crossbeam_utils::Backoff;
+
+    use super::{Notify, PROBE, sys};
+
+    /// State shared with the reactor on behalf of a parked task.
+    struct Slot {
+        done: AtomicBool,
+        /// The task has been parked for a [`PROBE`] period at least.
+        idle: AtomicBool,
+        waker: spin::Mutex<Waker>,
+    }
+
+    impl Slot {
+        fn fire(&self, idle: bool) {
+            self.idle.store(idle, Ordering::Relaxed);
+            self.done.store(true, Ordering::Release);
+            self.waker.lock().wake_by_ref();
+        }
+    }
+
+    /// A parked task, waiting for the word at `addr` to leave `key`.
+    struct Wait {
+        addr: usize,
+        key: u32,
+        slot: Arc<Slot>,
+    }
+
+    /// The thread sleeping on the futex words of every parked task of the process.
+    ///
+    /// It only passes the addresses of the words to the kernel and never reads them, so it stays
+    /// sound even if a mapping goes away before its waits are withdrawn. Every [`PROBE`] period
+    /// while some task is parked, it wakes them all as idle, for them to probe their peers.
+    struct Reactor {
+        /// Bumped to make the thread pick up a new wait.
+        control: AtomicU32,
+        waits: spin::Mutex<Vec<Wait>>,
+    }
+
+    static REACTOR: Reactor = Reactor {
+        control: AtomicU32::new(0),
+        waits: spin::Mutex::new(Vec::new()),
+    };
+
+    impl Reactor {
+        /// Returns the reactor of the process, started on first use.
+        fn get() -> Option<&'static Self> {
+            static STARTED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+            let started = STARTED.get_or_init(|| {
+                std::thread::Builder::new()
+                    .name("evering-park".into())
+                    .spawn(|| REACTOR.run())
+                    .is_ok()
+            });
+            started.then_some(&REACTOR)
+        }
+
+        fn park(&self, addr: usize, key: u32, slot: Arc<Slot>) {
+            self.waits.lock().push(Wait { addr, key, slot });
+            self.control.fetch_add(1, Ordering::SeqCst);
+            sys::wake(&self.control, 1);
+        }
+
+        /// Withdraws the wait of `slot`, the reactor may still sleep on its word until the next
+        /// wakeup but never wakes its task.
+        fn withdraw(&self, slot: &Arc<Slot>) {
+            self.waits.lock().retain(|w| !Arc::ptr_eq(&w.slot, slot));
+        }
+
+        fn run(&self) -> ! {
+            let mut words = Vec::new();
+            let mut fired = Vec::new();
+            let mut probed = std::time::Instant::now();
+            loop {
+                words.clear();
+                words.push((
+                    &self.control as *const AtomicU32,
+                    self.control.load(Ordering::SeqCst),
+                ));
+                for w in self.waits.lock().iter() {
+                    let word = (w.addr as *const AtomicU32, w.key);
+                    if !words.contains(&word) {
+                        words.push(word);
+                    }
+                }
+
+                // Nobody to probe for, the period starts with the next wait.
+                let timeout = if words.len() == 1 {
+                    probed = std::time::Instant::now();
+                    None
+                } else {
+                    Some(PROBE.saturating_sub(probed.elapsed()))
+                };
+                let woken = sys::wait_index(&words, timeout);
+                let idle = timeout.is_some() && probed.elapsed() >= PROBE;
+                let mut waits = self.waits.lock();
+                match woken {
+                    _ if idle => {
+                        probed = std::time::Instant::now();
+                        fired.append(&mut waits);
+                    }
+                    // A new wait, picked up by the next round.
+                    Some(0) => {}
+                    Some(i) => {
+                        let addr = words[i].0.addr();
+                        fired.extend(waits.extract_if(.., |w| w.addr == addr));
+                    }
+                    // Some word changed before sleeping, or the wait degraded: every task polls
+                    // again and parks anew if it must.
+                    None => fired.append(&mut waits),
+                }
+                drop(waits);
+                fired.drain(..).for_each(|w| w.slot.fire(idle));
+            }
+        }
+    }
+
+    /// Spin-then-park policy for a future waiting on a [`Notify`].
+    ///
+    /// A future first retries with a `Backoff` to keep the low-latency path. Once the backoff is
+    /// exhausted it registers as a waiter and hands the futex wait to a reactor thread shared by
+    /// the whole process, which wakes the task when the peer process notifies.
+    pub struct Park {
+        backoff: Backoff,
+        registered: bool,
+        slot: Option<Arc<Slot>>,
+    }
+
+    impl Park {
+        pub fn new() -> Self {
+            Self {
+                backoff: Backoff::new(),
+                registered: false,
+                slot: None,
+            }
+        }
+
+        /// Polls `f` until it yields, parking on `notify` in between.
+        ///
+        /// Calls `idle` before retrying once the task has been parked for a [`PROBE`] period, so
+        /// that the caller notices a peer gone without notifying.
+        pub fn poll<R>(
+            &mut self,
+            notify: &Notify,
+            cx: &mut Context<'_>,
+            idle: impl FnOnce(),
+            mut f: impl FnMut() -> Option<R>,
+        ) -> Poll<R> {
+            let mut idle = Some(idle);
+            loop {
+                if let Some(r) = f() {
+                    self.release(notify);
+                    return Poll::Ready(r);
+                }
+
+                if !self.backoff.is_completed() {
+                    self.backoff.snooze();
+                    continue;
+                }
+
+                if let Some(slot) = &self.slot {
+                    if !slot.done.load(Ordering::Acquire) {
+                        // Spurious poll, the reactor still waits for us.
+                        let mut waker = slot.waker.lock();
+                        if !waker.will_wake(cx.waker()) {
+                            *waker = cx.waker().clone();
+                        }
+                        return Poll::Pending;
+                    }
+                    if slot.idle.load(Ordering::Relaxed)
+                        && let Some(idle) = idle.take()
+                    {
+                        idle();
+                    }
+                    self.slot = None;
+                }
+
+                if self.registered {
+                    notify.unregister();
+                }
+                let key = notify.register();
+                self.registered = true;
+
+                if let Some(r) = f() {
+                    self.release(notify);
+                    return Poll::Ready(r);
+                }
+
+                self.sleep(notify, key, cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+
+        /// Wakes the task once `notify` is notified unconditionally, e.g. the queue is closed,
+        /// without advertising a waiter to the regular notifications.
+        ///
+        /// Yields once per wakeup, the caller re-checks its condition and watches again. Calls
+        /// `idle` first if the task has been watching for a [`PROBE`] period, as [`Park::poll`].
+        #[cfg(feature = "tokio")]
+        pub fn watch(
+            &mut self,
+            notify: &Notify,
+            cx: &mut Context<'_>,
+            idle: impl FnOnce(),
+        ) -> Poll<()> {
+            if let Some(slot) = &self.slot {
+                if slot.done.load(Ordering::Acquire) {
+                    if slot.idle.load(Ordering::Relaxed) {
+                        idle();
+                    }
+                    self.slot = None;
+                    return Poll::Ready(());
+                }
+                let mut waker = slot.waker.lock();
+                if !waker.will_wake(cx.waker()) {
+                    *waker = cx.waker().clone();
+                }
+                return Poll::Pending;
+            }
+            let key = notify.seq.load(Ordering::SeqCst);
+            self.sleep(notify, key, cx.waker().clone());
+            Poll::Pending
+        }
+
+        fn sleep(&mut self, notify: &Notify, key: u32, waker: Waker) {
+            let Some(reactor) = Reactor::get() else {
+                // Degrade to a busy poll rather than hanging the task.
+                waker.wake();
+                return;
+            };
+            let slot = Arc::new(Slot {
+                done: AtomicBool::new(false),
+                idle: AtomicBool::new(false),
+                waker: spin::Mutex::new(waker),
+            });
+            reactor.park((&notify.seq as *const AtomicU32).addr(), key, slot.clone());
+            self.slot = Some(slot);
+        }
+
+        fn release(&mut self, notify: &Notify) {
+            if self.registered {
+                notify.unregister();
+                self.registered = false;
+            }
+            if let Some(slot) = self.slot.take()
+                && !slot.done.load(Ordering::Acquire)
+                && let Some(reactor) = Reactor::get()
+            {
+                reactor.withdraw(&slot);
+            }
+            self.backoff.reset();
+        }
+
+        /// Withdraws the registration on `notify` when the future is dropped before completion.
+        pub fn cancel(&mut self, notify: &Notify) {
+            self.release(notify);
+        }
+    }
+
+    impl Default for Park {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
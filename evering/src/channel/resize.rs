@@ -0,0 +1,211 @@
+//! Quiescence of a queue, so that its slots can move to a buffer of another capacity.
+//!
+//! Every operation on the slots holds a [`Pass`] of its side. A resize raises a flag that holds
+//! back new operations, waits for the passes in flight to drop, then migrates the pending items
+//! while nobody can reach the old buffer. Without the `resize` feature a pass is free.
+
+use core::marker::PhantomData;
+#[cfg(feature = "resize")]
+use core::sync::atomic::Ordering;
+use core::sync::atomic::{AtomicBool, AtomicU32};
+
+#[cfg(feature = "resize")]
+use std::time::Instant;
+
+#[cfg(feature = "resize")]
+use crossbeam_utils::Backoff;
+use crossbeam_utils::CachePadded;
+
+use super::Header;
+#[cfg(feature = "resize")]
+use super::Slot;
+#[cfg(feature = "resize")]
+use super::cross::ResizeError;
+
+/// The side of a queue an operation works on.
+#[derive(Clone, Copy)]
+pub(super) enum Side {
+    Tx,
+    Rx,
+}
+
+/// Marks an operation in progress on the slots until dropped.
+pub(super) struct Pass<'a> {
+    #[cfg(feature = "resize")]
+    active: &'a AtomicU32,
+    _marker: PhantomData<&'a Header>,
+}
+
+impl Header {
+    /// Enters an operation on the slots, waiting out a resize in progress.
+    #[inline(always)]
+    pub(super) fn enter(&self, side: Side) -> Pass<'_> {
+        #[cfg(feature = "resize")]
+        {
+            self.active.enter(side)
+        }
+        #[cfg(not(feature = "resize"))]
+        {
+            let _ = side;
+            Pass {
+                _marker: PhantomData,
+            }
+        }
+    }
+}
+
+/// The operations in progress, each side on its own line so that they do not contend.
+///
+/// Laid out without the `resize` feature as well, but never maintained.
+#[cfg_attr(not(feature = "resize"), allow(dead_code))]
+pub(super) struct Active {
+    tx: CachePadded<AtomicU32>,
+    rx: CachePadded<AtomicU32>,
+    resizing: AtomicBool,
+}
+
+impl Active {
+    pub(super) const fn new() -> Self {
+        Self {
+            tx: CachePadded::new(AtomicU32::new(0)),
+            rx: CachePadded::new(AtomicU32::new(0)),
+            resizing: AtomicBool::new(false),
+        }
+    }
+}
+
+#[cfg(feature = "resize")]
+impl Active {
+    #[inline]
+    fn enter(&self, side: Side) -> Pass<'_> {
+        let active = match side {
+            Side::Tx => &*self.tx,
+            Side::Rx => &*self.rx,
+        };
+        let backoff = Backoff::new();
+        loop {
+            // Either the resize observes the count, or the count observes the resize.
+            active.fetch_add(1, Ordering::SeqCst);
+            if !self.resizing.load(Ordering::SeqCst) {
+                return Pass {
+                    active,
+                    _marker: PhantomData,
+                };
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+            while self.resizing.load(Ordering::Acquire) {
+                backoff.snooze();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "resize")]
+impl Drop for Pass<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::Release);
+    }
+}
+
+/// Holds back every operation on the queue until dropped.
+#[cfg(feature = "resize")]
+pub(super) struct Quiesced<'a> {
+    header: &'a Header,
+}
+
+#[cfg(feature = "resize")]
+impl Header {
+    /// Waits until no operation is in progress and holds back the new ones.
+    ///
+    /// Fails if another resize is in progress, or if an operation is still in progress at
+    /// `deadline`, the wait being unbounded without one. A pass held by a process gone in the
+    /// middle of an operation is never released, nor is one held by this very thread, such as
+    /// an open [`SlotGuard`](super::SlotGuard).
+    pub(super) fn quiesce(&self, deadline: Option<Instant>) -> Result<Quiesced<'_>, ResizeError> {
+        let active = &self.active;
+        if active
+            .resizing
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(ResizeError::Busy);
+        }
+        // Lifts the flag again on timeout.
+        let quiesced = Quiesced { header: self };
+
+        let backoff = Backoff::new();
+        while active.tx.load(Ordering::SeqCst) != 0 || active.rx.load(Ordering::SeqCst) != 0 {
+            if backoff.is_completed() && deadline.is_some_and(|d| Instant::now() >= d) {
+                return Err(ResizeError::Timeout);
+            }
+            backoff.snooze();
+        }
+        Ok(quiesced)
+    }
+}
+
+#[cfg(feature = "resize")]
+impl Drop for Quiesced<'_> {
+    fn drop(&mut self) {
+        self.header.active.resizing.store(false, Ordering::SeqCst);
+        // Senders parked on a full queue may fit now.
+        self.header.writable.notify();
+    }
+}
+
+#[cfg(feature = "resize")]
+impl Quiesced<'_> {
+    /// The number of pending items, released reservations included.
+    pub(super) fn len(&self) -> usize {
+        let header = self.header;
+        let head = header.head.load(Ordering::Relaxed);
+        let tail = header.tail.load(Ordering::Relaxed);
+        super::spsc::distance(header, head, tail)
+    }
+
+    /// Moves the pending items of `old` into `new` in order, and resets the queue to `new`.
+    ///
+    /// Fails with the number of pending items if they do not fit.
+    ///
+    /// # Safety
+    ///
+    /// `old` must be the current buffer of the queue, `new` a buffer of vacant slots stamped
+    /// with their index.
+    pub(super) unsafe fn migrate<T>(&self, old: &[Slot<T>], new: &[Slot<T>]) -> Result<(), usize> {
+        let header = self.header;
+        let len = self.len();
+        if len > new.len() {
+            return Err(len);
+        }
+        let head = header.head.load(Ordering::Relaxed);
+
+        let cap = header.cap();
+        let hix = head & (header.one_lap() - 1);
+        let mut moved = 0;
+        for i in 0..len {
+            let slot = &old[(hix + i) % cap];
+            if slot.abandoned.load(Ordering::Relaxed) {
+                // A released reservation, nothing to carry over.
+                slot.abandoned.store(false, Ordering::Relaxed);
+                continue;
+            }
+            let dst = &new[moved];
+            unsafe { dst.value.get().write(slot.value.get().read()) };
+            // Written at `{ lap: 0, index: moved }`.
+            dst.stamp.store(moved + 1, Ordering::Relaxed);
+            moved += 1;
+        }
+
+        // One lap is the smallest power of two greater than `cap`.
+        let one_lap = (new.len() + 1).next_power_of_two();
+        let tail = if moved < new.len() { moved } else { one_lap };
+        header.cap.store(new.len(), Ordering::Relaxed);
+        header.one_lap.store(one_lap, Ordering::Relaxed);
+        header.head.store(0, Ordering::Relaxed);
+        header.tail.store(tail, Ordering::Relaxed);
+        header.head_cache.store(0, Ordering::Relaxed);
+        header.tail_cache.store(tail, Ordering::Relaxed);
+        Ok(())
+    }
+}
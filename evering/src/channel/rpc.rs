@@ -0,0 +1,280 @@
+//! Typed request/response calls, the runtime of the [`service!`](crate::service) macro.
+//!
+//! A request is a [`MoveMsg`] tagged with the [`Method`] it calls, and its response is a
+//! [`MoveMsg`] of the return type of that method. Both are checked against their [`TypeId`] on
+//! arrival, a mismatched payload is freed without running its destructor.
+//!
+//! Outside of a service, [`Sx::call`] submits a typed request with any header.
+
+use crate::boxed::PBox;
+use crate::mem::MemAllocator;
+use crate::msg::{Envelope, Message, Move, MoveMsg, Tag, TypeId};
+use crate::token::{PackToken, Token};
+
+use super::driver::{AsyncSubmitter, Identifier, OpError, OwnOp, SubmitError, Sx};
+
+/// The envelope of a request, tagged with the method it calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Method(pub TypeId);
+
+impl Method {
+    /// Identifies `method` of `service` by the hash of `service::method`.
+    ///
+    /// The [`service!`](crate::service) macro names a service by its path, e.g.
+    /// `my_crate::calc::Calc`, so that services of the same name in two modules differ.
+    pub const fn of(service: &str, method: &str) -> Self {
+        use crate::msg::type_id::path_id;
+        Self(path_id(&[service, method]))
+    }
+}
+
+impl Envelope for Method {
+    #[inline]
+    fn control() -> Option<Self> {
+        Some(Self::default())
+    }
+}
+
+impl Tag<TypeId> for Method {
+    #[inline]
+    fn with_tag(self, value: TypeId) -> Self {
+        Self(value)
+    }
+
+    #[inline]
+    fn tag(&self) -> TypeId {
+        self.0
+    }
+}
+
+#[derive(Debug)]
+pub enum CallError<E> {
+    /// The request is not submitted, e.g. [`SubmitError`].
+    Submit(E),
+    /// The request is submitted but never answered, see [`OpError`].
+    Op(OpError),
+    /// The server answered with another type than expected, e.g. it does not know the method.
+    TypeMismatch { expected: TypeId, got: TypeId },
+}
+
+/// Resolves `token` as a `T`, freeing the payload of another type.
+pub fn open<T, A>(token: Token<A::Meta>, alloc: &A) -> Option<PBox<T, A>>
+where
+    T: Message<Semantics = Move>,
+    A: MemAllocator + Clone,
+{
+    if token.as_ptr::<T, _>(alloc).is_none() {
+        token.discard(alloc);
+        return None;
+    }
+    MoveMsg::detoken(token, alloc.clone())
+}
+
+/// Resolves the response `token` as a `T` like [`open`], telling which type it holds otherwise.
+pub fn expect<T, A, E>(token: Token<A::Meta>, alloc: &A) -> Result<PBox<T, A>, CallError<E>>
+where
+    T: Message<Semantics = Move>,
+    A: MemAllocator + Clone,
+{
+    let got = token.type_id();
+    open(token, alloc).ok_or(CallError::TypeMismatch {
+        expected: T::TYPE_ID,
+        got,
+    })
+}
+
+/// Calls `method` with `request`, parking until it is submitted, then awaiting a response of
+/// type `Resp`.
+///
+/// The header is usually a [`Method`], but any [`Envelope`] the server dispatches on will do.
+pub async fn call<X, H, A, Req, Resp>(
+    submitter: &X,
+    method: H,
+    request: Req,
+    alloc: &A,
+) -> Result<PBox<Resp, A>, CallError<X::AsyncError>>
+where
+    X: AsyncSubmitter<OwnOp<PackToken<H, A::Meta>>, PackToken<H, A::Meta>>,
+    H: Envelope,
+    A: MemAllocator + Clone,
+    Req: Message<Semantics = Move>,
+    Resp: Message<Semantics = Move>,
+{
+    let (token, _) = MoveMsg::new(request, alloc.clone());
+    let op = submitter
+        .submit(token.with(method))
+        .await
+        .map_err(CallError::Submit)?;
+    let (token, _) = op.await.map_err(CallError::Op)?.unpack();
+    expect(token, alloc)
+}
+
+impl<S, H, M> Sx<S, PackToken<H, M>>
+where
+    S: super::AsyncSender,
+    S::Item: Identifier<PackToken<H, M>>,
+    H: Envelope + Default,
+    M: crate::mem::Meta,
+{
+    /// Submits `request` with a default header like [`Sx::submit`], awaiting a response of
+    /// type `Resp`, e.g. `sx.call::<Req, Resp, _>(request, &alloc)`.
+    ///
+    /// The response is boxed in a clone of `alloc`. A request not submitted is handed back in
+    /// [`CallError::Submit`].
+    pub async fn call<Req, Resp, A>(
+        &self,
+        request: Req,
+        alloc: &A,
+    ) -> Result<PBox<Resp, A>, CallError<SubmitError<S::Error, PackToken<H, M>>>>
+    where
+        A: MemAllocator<Meta = M> + Clone,
+        Req: Message<Semantics = Move>,
+        Resp: Message<Semantics = Move>,
+    {
+        call(self, H::default(), request, alloc).await
+    }
+}
+
+/// Serves `request` with `f` if it is a `Req`, answering a null token otherwise.
+pub async fn handle<Req, Resp, A>(
+    request: Token<A::Meta>,
+    alloc: &A,
+    f: impl AsyncFnOnce(PBox<Req, A>) -> Resp,
+) -> Token<A::Meta>
+where
+    A: MemAllocator + Clone,
+    Req: Message<Semantics = Move>,
+    Resp: Message<Semantics = Move>,
+{
+    match open(request, alloc) {
+        Some(request) => MoveMsg::new(f(request).await, alloc.clone()).0,
+        None => Token::null(),
+    }
+}
+
+/// Defines a typed RPC service, from a trait whose methods take and return a [`Message`].
+///
+/// The allocator flavor comes first, e.g. `in evering::perlude::arena;`. For a trait `Calc`,
+/// the macro generates:
+///
+/// - the trait `Calc<A>`, implemented by the server with `async fn`s taking the request boxed
+///   in the allocator `A`;
+/// - `CalcClient`, the stub calling each method through an `AsyncSubmitter` and resolving the
+///   boxed response;
+/// - `CalcServer`, the `Service` dispatching each request to its method by the `Method` tag of
+///   its envelope, ready for `serve`.
+///
+/// The session carries requests of header `ReqId<Method>`. Each method is keyed by its name and
+/// the path of the trait, see [`Method::of`].
+///
+/// [`Message`]: crate::msg::Message
+#[macro_export]
+macro_rules! service {
+    (
+        in $($flavor:ident)::+;
+        $(#[$attr:meta])*
+        $vis:vis trait $name:ident {
+            $(
+                $(#[$mattr:meta])*
+                fn $method:ident($req:ty) -> $resp:ty;
+            )*
+        }
+    ) => {
+        $crate::__paste! {
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            $vis mod [<__ $name:snake>] {
+                pub use $($flavor)::+::{MemAllocator, channel};
+            }
+
+            $(#[$attr])*
+            $vis trait $name<A: [<__ $name:snake>]::MemAllocator + Clone> {
+                $(
+                    $(#[$mattr])*
+                    fn $method(
+                        &self,
+                        request: $crate::boxed::PBox<$req, A>,
+                    ) -> impl ::core::future::Future<Output = $resp>;
+                )*
+            }
+
+            #[doc = concat!("The client stub of [`", stringify!($name), "`].")]
+            $vis struct [<$name Client>]<X, A> {
+                submitter: X,
+                alloc: A,
+            }
+
+            impl<X, A> [<$name Client>]<X, A>
+            where
+                X: [<__ $name:snake>]::channel::AsyncSubmitter<
+                    [<__ $name:snake>]::channel::OwnOp<[<__ $name:snake>]::channel::Method>,
+                    [<__ $name:snake>]::channel::MsgToken<[<__ $name:snake>]::channel::Method>,
+                >,
+                A: [<__ $name:snake>]::MemAllocator + Clone,
+            {
+                $vis fn new(submitter: X, alloc: A) -> Self {
+                    Self { submitter, alloc }
+                }
+
+                $(
+                    $(#[$mattr])*
+                    $vis async fn $method(
+                        &self,
+                        request: $req,
+                    ) -> Result<
+                        $crate::boxed::PBox<$resp, A>,
+                        [<__ $name:snake>]::channel::CallError<X::AsyncError>,
+                    > {
+                        const METHOD: [<__ $name:snake>]::channel::Method =
+                            [<__ $name:snake>]::channel::Method::of(
+                                concat!(module_path!(), "::", stringify!($name)),
+                                stringify!($method),
+                            );
+                        [<__ $name:snake>]::channel::rpc::call(
+                            &self.submitter,
+                            METHOD,
+                            request,
+                            &self.alloc,
+                        )
+                        .await
+                    }
+                )*
+            }
+
+            #[doc = concat!("The dispatcher of [`", stringify!($name), "`] requests.")]
+            $vis struct [<$name Server>]<T>(pub T);
+
+            impl<T, A> [<__ $name:snake>]::channel::Service<A> for [<$name Server>]<T>
+            where
+                T: $name<A>,
+                A: [<__ $name:snake>]::MemAllocator + Clone,
+            {
+                type Request = [<__ $name:snake>]::channel::MsgToken<[<__ $name:snake>]::channel::Method>;
+                type Response = [<__ $name:snake>]::channel::MsgToken<[<__ $name:snake>]::channel::Method>;
+
+                async fn call(&self, request: Self::Request, alloc: &A) -> Self::Response {
+                    let (token, method) = request.unpack();
+                    $(
+                        const [<$method:upper>]: [<__ $name:snake>]::channel::Method =
+                            [<__ $name:snake>]::channel::Method::of(
+                                concat!(module_path!(), "::", stringify!($name)),
+                                stringify!($method),
+                            );
+                        if method == [<$method:upper>] {
+                            let token = [<__ $name:snake>]::channel::rpc::handle(
+                                token,
+                                alloc,
+                                async |request| self.0.$method(request).await,
+                            )
+                            .await;
+                            return token.with(method);
+                        }
+                    )*
+                    // Nobody serves the method.
+                    token.discard(alloc);
+                    [<__ $name:snake>]::channel::Token::null().with(method)
+                }
+            }
+        }
+    };
+}
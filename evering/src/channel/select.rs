@@ -0,0 +1,158 @@
+use alloc::vec::Vec;
+
+use crate::channel::{Notify, Queue, QueueChannel, QueueRx};
+
+/// A receiver watched by a [`Poller`].
+pub trait Pollable {
+    /// Returns `true` if a receive would not find the receiver empty: an item is queued, or the
+    /// queue is closed.
+    fn is_ready(&self) -> bool;
+
+    /// The futex word notified once the receiver may become ready.
+    fn readable(&self) -> &Notify;
+
+    /// Notices a peer gone without notifying, called once the poller has blocked for a while.
+    fn probe(&self) {}
+}
+
+impl<T: Queue> Pollable for QueueRx<T> {
+    #[inline]
+    fn is_ready(&self) -> bool {
+        !self.is_empty() || self.is_close()
+    }
+
+    #[inline]
+    fn readable(&self) -> &Notify {
+        self.rx.header().readable()
+    }
+
+    #[cfg(feature = "unix")]
+    fn probe(&self) {
+        QueueChannel::probe(self);
+    }
+}
+
+/// Readiness over many receivers, e.g. a server holding the receiver of every client duplex.
+///
+/// Receivers may come from different registry entries, or even different sessions, and are
+/// identified by the key returned on [`Poller::register`]. Each scan resumes right after the
+/// receiver reported first by the previous one, so a client that is always ready cannot starve
+/// the others.
+pub struct Poller<'a> {
+    entries: Vec<Option<&'a dyn Pollable>>,
+    cursor: usize,
+}
+
+impl Default for Poller<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Poller<'a> {
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Watches `rx`, returning its key. Keys of deregistered receivers are reused.
+    pub fn register(&mut self, rx: &'a dyn Pollable) -> usize {
+        match self.entries.iter().position(Option::is_none) {
+            Some(key) => {
+                self.entries[key] = Some(rx);
+                key
+            }
+            None => {
+                self.entries.push(Some(rx));
+                self.entries.len() - 1
+            }
+        }
+    }
+
+    pub fn deregister(&mut self, key: usize) -> Option<&'a dyn Pollable> {
+        self.entries.get_mut(key)?.take()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(Option::is_none)
+    }
+
+    /// Scans every receiver from the cursor, calling `f` with the ready ones until it returns
+    /// `false`. The cursor moves past the first ready receiver.
+    fn scan(&mut self, mut f: impl FnMut(usize) -> bool) {
+        let n = self.entries.len();
+        let mut first = None;
+        for i in 0..n {
+            let key = (self.cursor + i) % n;
+            let Some(rx) = self.entries[key] else {
+                continue;
+            };
+            if !rx.is_ready() {
+                continue;
+            }
+            first.get_or_insert(key);
+            if !f(key) {
+                break;
+            }
+        }
+        if let Some(key) = first {
+            self.cursor = key + 1;
+        }
+    }
+
+    /// Returns the key of a ready receiver, if any.
+    pub fn try_select(&mut self) -> Option<usize> {
+        let mut ready = None;
+        self.scan(|key| {
+            ready = Some(key);
+            false
+        });
+        ready
+    }
+
+    /// Pushes the keys of all ready receivers into `out`, returning their number.
+    pub fn ready(&mut self, out: &mut Vec<usize>) -> usize {
+        let len = out.len();
+        self.scan(|key| {
+            out.push(key);
+            true
+        });
+        out.len() - len
+    }
+
+    /// Returns the key of a ready receiver, blocking the thread until one is.
+    ///
+    /// The thread waits on the futex words of all receivers at once and is woken by whichever
+    /// sender pushes first.
+    #[cfg(feature = "std")]
+    pub fn select(&mut self) -> usize {
+        assert!(!self.is_empty(), "no receiver to select");
+        self.select_deadline(None).expect("no deadline")
+    }
+
+    /// Selects as [`Poller::select`], giving up once `timeout` elapses.
+    #[cfg(feature = "std")]
+    pub fn select_timeout(&mut self, timeout: core::time::Duration) -> Option<usize> {
+        self.select_deadline(std::time::Instant::now().checked_add(timeout))
+    }
+
+    #[cfg(feature = "std")]
+    fn select_deadline(&mut self, deadline: Option<std::time::Instant>) -> Option<usize> {
+        let rxs = self.entries.iter().flatten().copied().collect::<Vec<_>>();
+        let notifies = rxs.iter().map(|rx| rx.readable()).collect::<Vec<_>>();
+        super::notify::block_on_any(
+            &notifies,
+            deadline,
+            || rxs.iter().for_each(|rx| rx.probe()),
+            || self.try_select(),
+        )
+    }
+}
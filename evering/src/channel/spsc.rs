@@ -0,0 +1,221 @@
+//! Single-producer single-consumer operations over the ring of a [`Queue`].
+//!
+//! Head and tail keep the `{ lap, index }` encoding of the MPMC algorithm so that `len`, `drop_in`
+//! and friends are shared, but slot stamps are left untouched: each side owns its index and only
+//! reads the index of the other side when its cached copy says the ring is full or empty.
+//!
+//! Indices are published with `SeqCst` stores, pairing with the registration of a parked peer on
+//! [`Notify`](super::Notify) like the CAS of the MPMC algorithm does.
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::sync::atomic::Ordering;
+
+use super::{Header, Queue};
+
+/// Returns the number of elements between `head` and `tail`.
+#[inline]
+pub(super) fn distance(header: &Header, head: usize, tail: usize) -> usize {
+    let hix = head & (header.one_lap() - 1);
+    let tix = tail & (header.one_lap() - 1);
+
+    if hix < tix {
+        tix - hix
+    } else if hix > tix {
+        header.cap() - hix + tix
+    } else if tail == head {
+        0
+    } else {
+        header.cap()
+    }
+}
+
+/// Moves `pos` forward by `n` slots, which must not cross the end of lap.
+#[inline]
+fn advance(header: &Header, pos: usize, n: usize) -> usize {
+    let index = pos & (header.one_lap() - 1);
+    let lap = pos & !(header.one_lap() - 1);
+    debug_assert!(index + n <= header.cap());
+
+    if index + n < header.cap() {
+        // Same lap, incremented index.
+        pos + n
+    } else {
+        // One lap forward, index wraps around to zero.
+        lap.wrapping_add(header.one_lap())
+    }
+}
+
+/// Returns the free slots at `tail`, refreshing the cached head if fewer than `want`.
+#[inline]
+fn vacancy(header: &Header, tail: usize, want: usize) -> usize {
+    let head = header.head_cache.load(Ordering::Relaxed);
+    let free = header.cap() - distance(header, head, tail);
+    if free >= want {
+        return free;
+    }
+
+    let head = header.head.load(Ordering::Acquire);
+    header.head_cache.store(head, Ordering::Relaxed);
+    header.cap() - distance(header, head, tail)
+}
+
+/// Returns the filled slots at `head`, refreshing the cached tail if fewer than `want`.
+#[inline]
+fn occupancy(header: &Header, head: usize, want: usize) -> usize {
+    let tail = header.tail_cache.load(Ordering::Relaxed);
+    let filled = distance(header, head, tail);
+    if filled >= want {
+        return filled;
+    }
+
+    let tail = header.tail.load(Ordering::Acquire);
+    header.tail_cache.store(tail, Ordering::Relaxed);
+    distance(header, head, tail)
+}
+
+pub(super) fn push<Q: Queue + ?Sized>(q: &Q, value: Q::Item) -> Result<(), Q::Item> {
+    let header = q.header();
+    let tail = header.tail.load(Ordering::Relaxed);
+    if vacancy(header, tail, 1) == 0 {
+        return Err(value);
+    }
+
+    let index = tail & (header.one_lap() - 1);
+    debug_assert!(index < q.buf().len());
+    let slot = unsafe { q.buf().get_unchecked(index) };
+    unsafe {
+        slot.value.get().write(MaybeUninit::new(value));
+    }
+    header
+        .tail
+        .store(advance(header, tail, 1), Ordering::SeqCst);
+    Ok(())
+}
+
+pub(super) fn pop<Q: Queue + ?Sized>(q: &Q) -> Option<Q::Item> {
+    let header = q.header();
+    let head = header.head.load(Ordering::Relaxed);
+    if occupancy(header, head, 1) == 0 {
+        return None;
+    }
+
+    let index = head & (header.one_lap() - 1);
+    debug_assert!(index < q.buf().len());
+    let slot = unsafe { q.buf().get_unchecked(index) };
+    let msg = unsafe { slot.value.get().read().assume_init() };
+    header
+        .head
+        .store(advance(header, head, 1), Ordering::SeqCst);
+    Some(msg)
+}
+
+/// Pushes the elements of `iter` as [`QueueOps::push_batch`], also telling whether `iter` ran
+/// out.
+///
+/// [`QueueOps::push_batch`]: super::QueueOps::push_batch
+pub(super) fn push_batch<Q, I>(q: &Q, iter: &mut I) -> (usize, bool)
+where
+    Q: Queue + ?Sized,
+    I: Iterator<Item = Q::Item>,
+{
+    let header = q.header();
+    let buf = q.buf();
+    let mut pushed = 0;
+
+    loop {
+        let (lower, upper) = iter.size_hint();
+        if upper == Some(0) {
+            return (pushed, true);
+        }
+        // Without a lower bound, a single slot is filled ahead of the element.
+        let remain = lower.max(1);
+
+        let tail = header.tail.load(Ordering::Relaxed);
+        let index = tail & (header.one_lap() - 1);
+        let run = remain
+            .min(vacancy(header, tail, remain))
+            .min(header.cap() - index);
+        if run == 0 {
+            return (pushed, false);
+        }
+
+        // Nothing is published before the tail moves, so a short `iter` just stops early.
+        let mut filled = 0;
+        while filled < run {
+            let Some(value) = iter.next() else { break };
+            debug_assert!(index + filled < buf.len());
+            let slot = unsafe { buf.get_unchecked(index + filled) };
+            unsafe {
+                slot.value.get().write(MaybeUninit::new(value));
+            }
+            filled += 1;
+        }
+        header
+            .tail
+            .store(advance(header, tail, filled), Ordering::SeqCst);
+        pushed += filled;
+        if filled < run {
+            return (pushed, true);
+        }
+    }
+}
+
+pub(super) fn pop_batch<Q: Queue + ?Sized>(q: &Q, out: &mut Vec<Q::Item>, max: usize) -> usize {
+    let header = q.header();
+    let buf = q.buf();
+    let mut popped = 0;
+
+    while popped < max {
+        let want = max - popped;
+        let head = header.head.load(Ordering::Relaxed);
+        let index = head & (header.one_lap() - 1);
+        let run = want
+            .min(occupancy(header, head, want))
+            .min(header.cap() - index);
+        if run == 0 {
+            break;
+        }
+
+        out.reserve(run);
+        for i in 0..run {
+            debug_assert!(index + i < buf.len());
+            let slot = unsafe { buf.get_unchecked(index + i) };
+            out.push(unsafe { slot.value.get().read().assume_init() });
+        }
+        header
+            .head
+            .store(advance(header, head, run), Ordering::SeqCst);
+        popped += run;
+    }
+
+    popped
+}
+
+/// Returns the tail if a slot is free there, the slot is published by [`commit`].
+pub(super) fn reserve<Q: Queue + ?Sized>(q: &Q) -> Option<usize> {
+    let header = q.header();
+    let tail = header.tail.load(Ordering::Relaxed);
+    (vacancy(header, tail, 1) != 0).then_some(tail)
+}
+
+pub(super) fn commit<Q: Queue + ?Sized>(q: &Q, tail: usize) {
+    let header = q.header();
+    header
+        .tail
+        .store(advance(header, tail, 1), Ordering::SeqCst);
+}
+
+/// Returns the head if a slot is filled there, the slot is released by [`ack`].
+pub(super) fn front<Q: Queue + ?Sized>(q: &Q) -> Option<usize> {
+    let header = q.header();
+    let head = header.head.load(Ordering::Relaxed);
+    (occupancy(header, head, 1) != 0).then_some(head)
+}
+
+pub(super) fn ack<Q: Queue + ?Sized>(q: &Q, head: usize) {
+    let header = q.header();
+    header
+        .head
+        .store(advance(header, head, 1), Ordering::SeqCst);
+}
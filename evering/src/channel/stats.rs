@@ -0,0 +1,97 @@
+//! Traffic counters of a queue, kept in its shared [`Header`](super::Header).
+//!
+//! Every attached process updates the same counters, so any of them, or an inspector mapping the
+//! region, reads the traffic of the queue as a whole. All counters are `Relaxed`: a snapshot is
+//! not atomic across them.
+//!
+//! The counters are laid out without the `stats` feature as well, but never maintained.
+#![cfg_attr(not(feature = "stats"), allow(dead_code))]
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crossbeam_utils::CachePadded;
+
+/// A snapshot of the traffic of a queue, see [`QueueChannel::stats`](super::QueueChannel::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// The items pushed since creation.
+    pub pushed: u64,
+    /// The items popped since creation.
+    pub popped: u64,
+    /// The pushes rejected on a full queue.
+    pub full: u64,
+    /// The pops that found the queue empty.
+    pub empty: u64,
+    /// The highest length observed after a push.
+    pub high_water: u64,
+}
+
+/// Counters written by the senders.
+struct Producer {
+    pushed: AtomicU64,
+    full: AtomicU64,
+    high_water: AtomicU64,
+}
+
+/// Counters written by the receivers.
+struct Consumer {
+    popped: AtomicU64,
+    empty: AtomicU64,
+}
+
+/// Both sides are padded apart, so that counting does not add false sharing between them.
+pub(super) struct Stats {
+    producer: CachePadded<Producer>,
+    consumer: CachePadded<Consumer>,
+}
+
+impl Stats {
+    pub(super) const fn new() -> Self {
+        Self {
+            producer: CachePadded::new(Producer {
+                pushed: AtomicU64::new(0),
+                full: AtomicU64::new(0),
+                high_water: AtomicU64::new(0),
+            }),
+            consumer: CachePadded::new(Consumer {
+                popped: AtomicU64::new(0),
+                empty: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Counts `n` pushed items, or a rejection if none, then the length the queue is left at.
+    #[inline]
+    pub(super) fn push(&self, n: usize, len: impl FnOnce() -> usize) {
+        let producer = &self.producer;
+        if n == 0 {
+            producer.full.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        producer.pushed.fetch_add(n as u64, Ordering::Relaxed);
+        producer
+            .high_water
+            .fetch_max(len() as u64, Ordering::Relaxed);
+    }
+
+    /// Counts `n` popped items, or an empty poll if none.
+    #[inline]
+    pub(super) fn pop(&self, n: usize) {
+        let consumer = &self.consumer;
+        if n == 0 {
+            consumer.empty.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        consumer.popped.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn snapshot(&self) -> QueueStats {
+        QueueStats {
+            pushed: self.producer.pushed.load(Ordering::Relaxed),
+            popped: self.consumer.popped.load(Ordering::Relaxed),
+            full: self.producer.full.load(Ordering::Relaxed),
+            empty: self.consumer.empty.load(Ordering::Relaxed),
+            high_water: self.producer.high_water.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -16,11 +16,15 @@
     ptr_metadata,
     slice_ptr_get,
     get_mut_unchecked,
-    unsafe_cell_access
+    unsafe_cell_access,
+    async_iterator
 )]
 
 extern crate alloc;
 
+#[doc(hidden)]
+pub use paste::paste as __paste;
+
 #[cfg(feature = "tracing")]
 extern crate tracing;
 
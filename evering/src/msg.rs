@@ -56,9 +56,15 @@ pub mod type_id {
         const TYPE_ID: TypeId = combine(fnv1a64("option"), T::TYPE_ID);
     }
 
+    const FNV_OFFSET: TypeId = 0xcbf29ce484222325;
+
     #[inline]
     const fn fnv1a64(s: &str) -> TypeId {
-        let mut hash = 0xcbf29ce484222325;
+        fnv1a64_extend(FNV_OFFSET, s)
+    }
+
+    #[inline]
+    const fn fnv1a64_extend(mut hash: TypeId, s: &str) -> TypeId {
         let bytes = s.as_bytes();
         let mut i = 0;
         while i < bytes.len() {
@@ -79,9 +85,23 @@ pub mod type_id {
         fnv1a64(s)
     }
 
+    /// Same as [`type_id`] of the segments joined by `::`, without joining them.
+    pub const fn path_id(segments: &[&str]) -> TypeId {
+        let mut hash = FNV_OFFSET;
+        let mut i = 0;
+        while i < segments.len() {
+            if i > 0 {
+                hash = fnv1a64_extend(hash, "::");
+            }
+            hash = fnv1a64_extend(hash, segments[i]);
+            i += 1;
+        }
+        hash
+    }
+
     #[cfg(test)]
     mod tests {
-        use super::{TypeTag, combine, type_id};
+        use super::{TypeTag, combine, path_id, type_id};
 
         #[test]
         fn hash_consistent() {
@@ -93,6 +113,13 @@ pub mod type_id {
             assert_ne!(id1, id3, "Type id shouldn't be same for different type");
         }
 
+        #[test]
+        fn path_joined() {
+            assert_eq!(path_id(&["a::B", "c"]), type_id("a::B::c"));
+            assert_eq!(path_id(&["a"]), type_id("a"));
+            assert_ne!(path_id(&["a", "bc"]), path_id(&["ab", "c"]));
+        }
+
         #[test]
         fn combine_consistent() {
             let a = type_id("A");
@@ -237,9 +264,27 @@ impl<T: Message<Semantics = Move>> MoveMsg<[T]> {
     }
 }
 
-pub trait Envelope {}
+pub trait Envelope {
+    /// Builds the envelope of a control message, such as the cancellation of a request, which
+    /// carries no request of its own.
+    ///
+    /// Requests in an envelope without one can be neither cancelled nor granted more chunks of
+    /// their stream, see [`Identified::cancel`](crate::channel::driver::Identified::cancel).
+    #[inline]
+    fn control() -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
 
-impl Envelope for () {}
+impl Envelope for () {
+    #[inline]
+    fn control() -> Option<Self> {
+        Some(())
+    }
+}
 
 pub trait Tag<T>: Envelope {
     fn with_tag(self, value: T) -> Self
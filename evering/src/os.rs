@@ -3,11 +3,14 @@
 #[cfg(feature = "unix")]
 pub mod unix;
 
+#[cfg(feature = "unix")]
 use self::unix::AddrSpec;
+#[cfg(feature = "unix")]
 use crate::mem::MapBuilder;
 
 pub struct FdBackend;
 
+#[cfg(feature = "unix")]
 impl MapBuilder<AddrSpec, FdBackend> {
     pub fn fd() -> Self {
         Self::from_backend(FdBackend)
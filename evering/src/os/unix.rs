@@ -1,4 +1,4 @@
-use core::{ffi::c_void, ptr::NonNull};
+use core::{ffi::c_void, ptr::NonNull, sync::atomic::AtomicU32, time::Duration};
 pub use nix::{
     libc::off_t,
     sys::mman::{MapFlags, ProtFlags},
@@ -41,6 +41,7 @@ enum FdKind {
     MemFd(String),    // ephemeral
     Shm(PathBuf),     // persistent in RAM
     Regular(PathBuf), //persistent in FS
+    EventFd,          // counter, no backing storage
     FromFd,           // Adopted
 }
 
@@ -113,6 +114,19 @@ impl UnixFd<OwnedFd> {
         unistd::unlink(&path)
     }
 
+    /// Creates a non-blocking event counter (eventfd), e.g. a doorbell for the peer process.
+    #[cfg(target_os = "linux")]
+    pub fn eventfd() -> nix::Result<Self> {
+        use nix::sys::eventfd::{EfdFlags, EventFd};
+        let flags = EfdFlags::EFD_NONBLOCK.union(EfdFlags::EFD_CLOEXEC);
+        let fd = EventFd::from_flags(flags)?;
+        Ok(Self {
+            fd: fd.into(),
+            kind: FdKind::EventFd,
+            size: 0,
+        })
+    }
+
     pub fn from_fd(fd: OwnedFd) -> nix::Result<Self> {
         use nix::sys::stat;
         let size = stat::fstat(fd.as_fd())?.st_size as usize;
@@ -247,6 +261,212 @@ impl mem::SharedMmap<AddrSpec> for FdBackend {
     }
 }
 
+/// Blocks the calling thread while the process-shared futex `word` still holds `expected`.
+///
+/// The word is addressed by pointer rather than reference: the kernel only compares it, and a
+/// word that has been unmapped meanwhile fails with `EFAULT` instead of faulting the caller.
+///
+/// Returns `false` if `timeout` elapsed before a wakeup.
+#[cfg(target_os = "linux")]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn futex_wait(word: *const AtomicU32, expected: u32, timeout: Option<Duration>) -> bool {
+    use nix::{errno::Errno, libc};
+
+    let ts = timeout.map(|d| libc::timespec {
+        tv_sec: d.as_secs() as libc::time_t,
+        tv_nsec: d.subsec_nanos() as _,
+    });
+    let ts = ts
+        .as_ref()
+        .map_or(core::ptr::null(), |ts| ts as *const libc::timespec);
+
+    // Not `FUTEX_PRIVATE_FLAG`: waiters and wakers live in different processes.
+    let res = unsafe { libc::syscall(libc::SYS_futex, word, libc::FUTEX_WAIT, expected, ts) };
+    !(res == -1 && Errno::last() == Errno::ETIMEDOUT)
+}
+
+/// Wakes at most `count` threads blocked on the process-shared futex `word`.
+///
+/// Returns the number of woken threads.
+#[cfg(target_os = "linux")]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub fn futex_wake(word: *const AtomicU32, count: u32) -> usize {
+    use nix::libc;
+
+    let count = count.min(i32::MAX as u32);
+    let res = unsafe { libc::syscall(libc::SYS_futex, word, libc::FUTEX_WAKE, count) };
+    res.max(0) as usize
+}
+
+/// Blocks the calling thread while every process-shared futex of `words` still holds its
+/// expected value.
+///
+/// At most 128 words are accepted. Returns the index of the woken word, or `None` if `timeout`
+/// elapsed before a wakeup. Fails with `EAGAIN` if a word changed before sleeping, and with
+/// `ENOSYS` on kernels before 5.16.
+#[cfg(target_os = "linux")]
+pub fn futex_waitv(
+    words: &[(*const AtomicU32, u32)],
+    timeout: Option<Duration>,
+) -> nix::Result<Option<usize>> {
+    use nix::{errno::Errno, libc};
+
+    /// `struct futex_waitv` of the kernel ABI.
+    #[repr(C)]
+    struct FutexWaitv {
+        val: u64,
+        uaddr: u64,
+        flags: u32,
+        reserved: u32,
+    }
+    const FUTEX2_SIZE_U32: u32 = 0x02;
+
+    let waiters = words
+        .iter()
+        .map(|&(word, expected)| FutexWaitv {
+            val: expected as u64,
+            uaddr: word.addr() as u64,
+            flags: FUTEX2_SIZE_U32,
+            reserved: 0,
+        })
+        .collect::<Vec<_>>();
+
+    // Unlike `futex_wait` the timeout is absolute.
+    let ts = match timeout {
+        Some(d) => {
+            let mut now = core::mem::MaybeUninit::<libc::timespec>::uninit();
+            Errno::result(unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, now.as_mut_ptr()) })?;
+            let now = unsafe { now.assume_init() };
+            let deadline = Duration::new(now.tv_sec as u64, now.tv_nsec as u32) + d;
+            Some(libc::timespec {
+                tv_sec: deadline.as_secs() as libc::time_t,
+                tv_nsec: deadline.subsec_nanos() as _,
+            })
+        }
+        None => None,
+    };
+    let ts = ts
+        .as_ref()
+        .map_or(core::ptr::null(), |ts| ts as *const libc::timespec);
+
+    let res = unsafe {
+        libc::syscall(
+            libc::SYS_futex_waitv,
+            waiters.as_ptr(),
+            waiters.len() as libc::c_uint,
+            0,
+            ts,
+            libc::CLOCK_MONOTONIC,
+        )
+    };
+    if res >= 0 {
+        return Ok(Some(res as usize));
+    }
+    match Errno::last() {
+        Errno::ETIMEDOUT => Ok(None),
+        e => Err(e),
+    }
+}
+
+/// Sends `fds` over the connected unix socket `sock`, e.g. a memfd and its doorbells to an
+/// unrelated process, which adopts them with [`recv_fds`].
+pub fn send_fds(sock: BorrowedFd<'_>, fds: &[BorrowedFd<'_>]) -> nix::Result<()> {
+    use nix::{errno::Errno, libc};
+    use std::os::fd::{AsRawFd, RawFd};
+
+    let raw = fds.iter().map(|fd| fd.as_raw_fd()).collect::<Vec<_>>();
+    let len = size_of_val(raw.as_slice()) as u32;
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(len) } as usize];
+    // A message of ancillary data only is not delivered everywhere, one byte rides along.
+    let mut byte = 0u8;
+    let mut iov = libc::iovec {
+        iov_base: (&raw mut byte).cast(),
+        iov_len: 1,
+    };
+    // Safety: a zeroed `msghdr` is valid, its buffers are set below.
+    let mut msg: libc::msghdr = unsafe { core::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = control.len() as _;
+    // Safety: `control` has room for one header and `raw`.
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(len) as _;
+        let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+        core::ptr::copy_nonoverlapping(raw.as_ptr(), data, raw.len());
+    }
+    Errno::result(unsafe { libc::sendmsg(sock.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) })?;
+    Ok(())
+}
+
+/// Receives at most `max` fds sent by [`send_fds`].
+///
+/// Fails with `EPIPE` once the peer is gone, and with `ENOBUFS` if more than `max` fds were
+/// sent, the extra ones being closed.
+pub fn recv_fds(sock: BorrowedFd<'_>, max: usize) -> nix::Result<Vec<UnixFd<OwnedFd>>> {
+    use nix::{errno::Errno, libc};
+    use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+
+    let len = (max * size_of::<RawFd>()) as u32;
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(len) } as usize];
+    let mut byte = 0u8;
+    let mut iov = libc::iovec {
+        iov_base: (&raw mut byte).cast(),
+        iov_len: 1,
+    };
+    // Safety: a zeroed `msghdr` is valid, its buffers are set below.
+    let mut msg: libc::msghdr = unsafe { core::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = control.len() as _;
+    let n = Errno::result(unsafe {
+        libc::recvmsg(sock.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC)
+    })?;
+    if n == 0 {
+        return Err(Errno::EPIPE);
+    }
+
+    let mut fds = Vec::new();
+    // Safety: the headers were written by the kernel within `control`.
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg).cast::<RawFd>();
+                let count =
+                    ((*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize) / size_of::<RawFd>();
+                for i in 0..count {
+                    fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    if msg.msg_flags & libc::MSG_CTRUNC != 0 {
+        return Err(Errno::ENOBUFS);
+    }
+    fds.into_iter().map(UnixFd::from_fd).collect()
+}
+
+/// Returns `false` if no process with `pid` exists, probing it with the null signal.
+pub fn process_alive(pid: u32) -> bool {
+    use nix::errno::Errno;
+    use nix::sys::signal;
+
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    // `EPERM` still proves the existence of the process.
+    !matches!(
+        signal::kill(unistd::Pid::from_raw(pid), None),
+        Err(Errno::ESRCH)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     #![cfg(target_os = "linux")]
@@ -305,6 +525,43 @@ mod tests {
         let _ = Mmap::unmap(&mut blk);
     }
 
+    #[test]
+    fn pass_fds() {
+        use std::os::fd::AsFd;
+        use std::os::unix::net::UnixStream;
+
+        use super::{recv_fds, send_fds};
+
+        const SIZE: usize = 4096;
+        const NAME: &str = "pass";
+        const VALUE: &[u8] = b"hello";
+
+        let (a, b) = UnixStream::pair().expect("should connect");
+        let fd = UnixFd::memfd(NAME, SIZE, false).expect("should create");
+        let bell = UnixFd::eventfd().expect("should create");
+        send_fds(a.as_fd(), &[fd.as_fd(), bell.as_fd()]).expect("should send");
+
+        let mut fds = recv_fds(b.as_fd(), 2).expect("should receive").into_iter();
+        let (received, _) = (fds.next().unwrap(), fds.next().unwrap());
+        assert_eq!(received.size(), SIZE);
+
+        let bk = FdBackend;
+        let mut blk = bk
+            .shared(SIZE, Access::READ | Access::WRITE, fd)
+            .expect("should create");
+        unsafe { blk.write(VALUE) };
+        let bk2 = FdBackend;
+        let mut peer = bk2
+            .shared(SIZE, Access::READ | Access::WRITE, received)
+            .expect("should create");
+        unsafe { assert_eq!(peer.read(VALUE.len()), VALUE) };
+        let _ = Mmap::unmap(&mut blk);
+        let _ = Mmap::unmap(&mut peer);
+
+        drop(a);
+        assert_eq!(recv_fds(b.as_fd(), 2).err(), Some(nix::errno::Errno::EPIPE));
+    }
+
     #[test]
     fn memfd_dup() {
         const SIZE: usize = 4096;
@@ -10,9 +10,10 @@ pub mod talc {
         numeric::Id,
         reg, talc,
     };
-    use channel::{MsgDuplex, MsgDuplexPeek, MsgDuplexView};
+    use channel::{MsgBroadcast, MsgDuplex, MsgDuplexPeek, MsgDuplexView, QueueConfig};
 
     pub use crate::mem::{Access, Accessible, MapBuilder, MemAllocInfo};
+    pub use crate::reg::EntryState;
 
     mod_channel! {
         channel,
@@ -30,6 +31,9 @@ pub mod talc {
 
     pub type RegistryHeader<H, const N: usize> = reg::Header<MsgDuplex<H>, N>;
     pub type MapRegistry<H, const N: usize, S, M> = reg::MapRegistry<MsgDuplex<H>, N, S, M>;
+    pub type BroadcastRegistryHeader<H, const N: usize> = reg::Header<MsgBroadcast<H>, N>;
+    pub type MapBroadcastRegistry<H, const N: usize, S, M> =
+        reg::MapRegistry<MsgBroadcast<H>, N, S, M>;
 
     pub struct SessionBy<H: Envelope, const N: usize> {
         _marker: PhantomData<H>,
@@ -73,8 +77,8 @@ pub mod talc {
     }
 
     impl<H: Envelope, const N: usize, S: AddrSpec, M: Mmap<S>> Session<H, N, S, M> {
-        pub fn prepare(&self, cap: usize) -> Option<Id> {
-            let Ok((id, _)) = self.reg.prepare(cap, self.alloc.as_ref()) else {
+        pub fn prepare(&self, conf: impl Into<QueueConfig>) -> Option<Id> {
+            let Ok((id, _)) = self.reg.prepare(conf.into(), self.alloc.as_ref()) else {
                 return None;
             };
 
@@ -90,6 +94,22 @@ pub mod talc {
             let (duplex, _) = self.reg.view(id, self.alloc.clone());
             duplex
         }
+
+        /// Iterates over the prepared duplexes, yielding `(id, state, rc)`, see
+        /// [`reg::Registry::entries`].
+        pub fn entries(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.entries()
+        }
+
+        /// Iterates over the duplexes borrowed right now, see [`Session::entries`].
+        pub fn active(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.active()
+        }
+
+        /// Iterates over the prepared duplexes nobody borrows, see [`Session::entries`].
+        pub fn inactive(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.inactive()
+        }
     }
 }
 
@@ -104,7 +124,7 @@ pub mod arena {
         numeric::Id,
         reg::{self},
     };
-    use channel::{MsgDuplex, MsgDuplexPeek, MsgDuplexView};
+    use channel::{MsgBroadcast, MsgDuplex, MsgDuplexPeek, MsgDuplexView, QueueConfig};
 
     mod_channel! {
         channel,
@@ -113,8 +133,9 @@ pub mod arena {
 
     pub use crate::arena::{Config, Optimistic, Pessimistic};
     pub use crate::mem::{Access, Accessible, MapBuilder, MemAllocInfo};
+    pub use crate::reg::EntryState;
 
-    trait MemAllocator = mem::MemAllocator<Meta = arena::Meta>;
+    pub trait MemAllocator = mem::MemAllocator<Meta = arena::Meta>;
 
     pub type AllocMetaConfig = arena::MetaConfig;
     pub type AllocHeader<G> = arena::Header<G>;
@@ -123,6 +144,9 @@ pub mod arena {
 
     pub type RegistryHeader<H, const N: usize> = reg::Header<MsgDuplex<H>, N>;
     pub type MapRegistry<H, const N: usize, S, M> = reg::MapRegistry<MsgDuplex<H>, N, S, M>;
+    pub type BroadcastRegistryHeader<H, const N: usize> = reg::Header<MsgBroadcast<H>, N>;
+    pub type MapBroadcastRegistry<H, const N: usize, S, M> =
+        reg::MapRegistry<MsgBroadcast<H>, N, S, M>;
 
     pub struct SessionBy<G: Strategy, H: Envelope, const N: usize> {
         _marker: PhantomData<(G, H)>,
@@ -179,8 +203,8 @@ pub mod arena {
     }
 
     impl<G: Strategy, H: Envelope, const N: usize, S: AddrSpec, M: Mmap<S>> Session<G, H, N, S, M> {
-        pub fn prepare(&self, cap: usize) -> Option<Id> {
-            let Ok((id, _)) = self.reg.prepare(cap, self.alloc.as_ref()) else {
+        pub fn prepare(&self, conf: impl Into<QueueConfig>) -> Option<Id> {
+            let Ok((id, _)) = self.reg.prepare(conf.into(), self.alloc.as_ref()) else {
                 return None;
             };
 
@@ -196,6 +220,22 @@ pub mod arena {
             let (duplex, _) = self.reg.view(id, self.alloc.clone());
             duplex
         }
+
+        /// Iterates over the prepared duplexes, yielding `(id, state, rc)`, see
+        /// [`reg::Registry::entries`].
+        pub fn entries(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.entries()
+        }
+
+        /// Iterates over the duplexes borrowed right now, see [`Session::entries`].
+        pub fn active(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.active()
+        }
+
+        /// Iterates over the prepared duplexes nobody borrows, see [`Session::entries`].
+        pub fn inactive(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+            self.reg.inactive()
+        }
     }
 }
 
@@ -210,13 +250,35 @@ macro_rules! mod_channel {
 
             use $crate::channel::driver::CachePoolHandle;
             use $crate::channel::{Receiver, Sender};
-            use $crate::channel::{cross, driver};
+            use $crate::channel::{broadcast, cross, driver};
             use $crate::msg::Envelope;
             use $crate::reg::{Entry, MapEntry};
             use $crate::token;
 
-            pub use $crate::channel::driver::{Completer, Submitter, TryCompState};
-            pub use $crate::channel::{QueueChannel, TryRecvError, TrySendError};
+            #[cfg(feature = "stats")]
+            pub use $crate::channel::QueueStats;
+            pub use $crate::channel::broadcast::{
+                RecvError as BroadcastRecvError, TryRecvError as BroadcastTryRecvError,
+            };
+            pub use $crate::channel::cross::QueueConfig;
+            #[cfg(feature = "resize")]
+            pub use $crate::channel::cross::ResizeError;
+            #[cfg(all(feature = "tokio", target_os = "linux"))]
+            pub use $crate::channel::doorbell::{BellRx, BellTx, Doorbell, DuplexBells};
+            #[cfg(feature = "tokio")]
+            pub use $crate::channel::driver::TokioTimer;
+            pub use $crate::channel::driver::{
+                AsyncSubmitter, CancelSet, CancelToken, Chain, ChainBuilder, Completer, DeadLetter,
+                Discard, Links, OpError, Service, StreamOp, Submitter, Timeout, Timer,
+                TryCompState, serve, serve_chain,
+            };
+            pub use $crate::channel::local;
+            pub use $crate::channel::rpc::{self, CallError, Method};
+            pub use $crate::channel::select::{Pollable, Poller};
+            pub use $crate::channel::{
+                AttachError, Mode, QueueChannel, ReadGuard, RecvError, RecvTimeoutError, SendError,
+                SendTimeoutError, SlotGuard, TryRecvError, TrySendError,
+            };
             pub use $crate::token::{ReqId, ReqNull};
 
             pub type Token = token::Token<Meta>;
@@ -236,28 +298,51 @@ macro_rules! mod_channel {
             pub type ReceiverView<H, R, S, M> =
                 cross::Sender<H, Meta, MapEntry<MsgDuplex<H>, S, M>, R>;
 
-            pub type SubmitterPeek<'a, H, const N: usize, R> =
-                driver::Sx<SenderPeek<'a, H, R>, MsgToken<H>, N>;
-            pub type CompleterPeek<'a, H, const N: usize, R> =
-                driver::Cx<ReceiverPeek<'a, H, R>, MsgToken<H>, N>;
-            pub type SubmitterView<H, const N: usize, R, S, M> =
-                driver::Sx<SenderView<H, R, S, M>, MsgToken<H>, N>;
-            pub type CompleterView<H, const N: usize, R, S, M> =
-                driver::Cx<ReceiverView<H, R, S, M>, MsgToken<H>, N>;
-            pub type TrySubmitError<H> = driver::TrySubmitError<TrySendError<OpMsgToken<H>>>;
-            pub type RefOp<'a, H, const N: usize> = driver::RefOp<'a, MsgToken<H>, N>;
-            pub type OwnOp<H, const N: usize> = driver::OwnOp<MsgToken<H>, N>;
+            pub type SharedToken<H> = broadcast::SharedToken<H, Meta>;
+            pub type MsgBroadcast<H> = broadcast::TokenBroadcast<H, Meta>;
+            pub type BroadcastPeek<'a, H> =
+                broadcast::BroadcastView<H, Meta, &'a Entry<MsgBroadcast<H>>>;
+            pub type BroadcastView<H, S, M> =
+                broadcast::BroadcastView<H, Meta, MapEntry<MsgBroadcast<H>, S, M>>;
+            pub type PublisherView<H, S, M> =
+                broadcast::Publisher<H, Meta, MapEntry<MsgBroadcast<H>, S, M>>;
+            pub type SubscriberView<H, S, M> =
+                broadcast::Subscriber<H, Meta, MapEntry<MsgBroadcast<H>, S, M>>;
+
+            #[cfg(all(feature = "tokio", target_os = "linux"))]
+            pub type BellSenderView<H, R, S, M> =
+                $crate::channel::doorbell::DuplexBellTx<H, Meta, MapEntry<MsgDuplex<H>, S, M>, R>;
+            #[cfg(all(feature = "tokio", target_os = "linux"))]
+            pub type BellReceiverView<H, R, S, M> =
+                $crate::channel::doorbell::DuplexBellRx<H, Meta, MapEntry<MsgDuplex<H>, S, M>, R>;
+
+            pub type SubmitterPeek<'a, H, R> = driver::Sx<SenderPeek<'a, H, R>, MsgToken<H>>;
+            pub type CompleterPeek<'a, H, R> = driver::Cx<ReceiverPeek<'a, H, R>, MsgToken<H>>;
+            pub type SubmitterView<H, R, S, M> = driver::Sx<SenderView<H, R, S, M>, MsgToken<H>>;
+            pub type CompleterView<H, R, S, M> = driver::Cx<ReceiverView<H, R, S, M>, MsgToken<H>>;
+            pub type TrySubmitError<H> =
+                driver::TrySubmitError<TrySendError<OpMsgToken<H>>, MsgToken<H>>;
+            pub type SubmitError<H> = driver::SubmitError<SendError<OpMsgToken<H>>, MsgToken<H>>;
+            pub type RefOp<'a, H> = driver::RefOp<'a, MsgToken<H>>;
+            pub type OwnOp<H> = driver::OwnOp<MsgToken<H>>;
+            pub type TimeoutOp<H, Tm> = driver::TimeoutOp<MsgToken<H>, Tm>;
 
             pub trait MsgSender<H: Envelope> =
                 Sender<Item = MsgToken<H>, TryError = TrySendError<MsgToken<H>>> + QueueChannel;
             pub trait MsgReceiver<H: Envelope> =
                 Receiver<Item = MsgToken<H>, TryError = TryRecvError> + QueueChannel;
 
-            pub type CachePool<H, const N: usize> = CachePoolHandle<MsgToken<H>, N>;
-            pub trait MsgSubmitter<H: Envelope, const N: usize> =
-                Submitter<OwnOp<H, N>, MsgToken<H>, Error = TrySubmitError<H>> + QueueChannel;
-            pub trait MsgCompleter<H: Envelope, const N: usize> =
+            pub type CachePool<H> = CachePoolHandle<MsgToken<H>>;
+            pub trait MsgSubmitter<H: Envelope> =
+                Submitter<OwnOp<H>, MsgToken<H>, Error = TrySubmitError<H>> + QueueChannel;
+            pub trait MsgAsyncSubmitter<H: Envelope> = AsyncSubmitter<
+                    OwnOp<H>,
+                    MsgToken<H>,
+                    Error = TrySubmitError<H>,
+                    AsyncError = SubmitError<H>,
+                > + QueueChannel;
+            pub trait MsgCompleter<H: Envelope> =
                 Completer<MsgToken<H>, Error = TryRecvError> + QueueChannel;
         }
     };
-}
\ No newline at end of file
+}
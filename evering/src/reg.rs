@@ -25,6 +25,15 @@ pub mod state {
     pub const DEINITIALIZING: u8 = 4;
 }
 
+/// The state of a prepared entry, see [`Registry::entries`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryState {
+    /// Borrowed by somebody.
+    Active,
+    /// Borrowed by nobody.
+    Inactive,
+}
+
 pub trait AsEntry<T>: const Deref<Target = Entry<T>> {}
 impl<H: const Deref<Target = Entry<T>>, T> AsEntry<T> for H {}
 
@@ -146,6 +155,31 @@ impl<T> Entry<T> {
         Some(live)
     }
 
+    /// Reads `(live, state, rc)`, retrying until neither the state nor the live count moved
+    /// while the counters were read.
+    ///
+    /// The rc is only as consistent as a borrow in flight allows, e.g. an entry just made
+    /// active may still count no borrow.
+    fn snapshot(&self) -> Option<(u32, EntryState, usize)> {
+        let backoff = Backoff::new();
+        loop {
+            let raw = self.state.load(Ordering::Acquire);
+            let state = match raw {
+                ACTIVE => EntryState::Active,
+                INACTIVE => EntryState::Inactive,
+                _ => return None,
+            };
+            let live = self.live.load(Ordering::Acquire);
+            let rc = self.rc.load(Ordering::Acquire);
+            if self.state.load(Ordering::Relaxed) == raw
+                && self.live.load(Ordering::Relaxed) == live
+            {
+                return Some((live, state, rc));
+            }
+            backoff.snooze();
+        }
+    }
+
     fn acquire<'a>(&'a self, id: &Id) -> Option<&'a Entry<T>> {
         let backoff = Backoff::new();
 
@@ -425,6 +459,30 @@ impl<T: Finalize, const N: usize> Registry<T, N> {
         Some(Id { idx, live })
     }
 
+    /// Iterates over the initiated entries, yielding `(id, state, rc)`.
+    ///
+    /// Each entry is read on its own while others may be prepared or cleared, the snapshot is
+    /// outdated as soon as it is taken, e.g. borrowing its id may fail. The id and state of an
+    /// entry are read together, its rc may lag a borrow in flight.
+    pub fn entries(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+        self.entries.iter().enumerate().filter_map(|(idx, e)| {
+            let (live, state, rc) = e.snapshot()?;
+            Some((Id { idx, live }, state, rc))
+        })
+    }
+
+    /// Iterates over the entries borrowed right now, see [`Registry::entries`].
+    pub fn active(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+        self.entries()
+            .filter(|&(_, state, _)| state == EntryState::Active)
+    }
+
+    /// Iterates over the entries nobody borrows, see [`Registry::entries`].
+    pub fn inactive(&self) -> impl Iterator<Item = (Id, EntryState, usize)> + '_ {
+        self.entries()
+            .filter(|&(_, state, _)| state == EntryState::Inactive)
+    }
+
     #[inline]
     fn reserve<'a>(r: &'a Self, id: &Id) -> Option<&'a Entry<T>> {
         let entry = &r.entries[id.idx];
@@ -780,6 +838,62 @@ mod tests {
         )
     }
 
+    #[test]
+    fn enumerate() {
+        use std::thread;
+
+        use super::EntryState;
+
+        const N: usize = 8;
+        const THREAD_NUM: usize = 4;
+        const ROUNDS: usize = 200;
+
+        tracing_init();
+        let reg = mock_reg::<MockResource, N>();
+        let ctx = MockResource::mock_ctx();
+
+        let (h1, ctx) = reg.prepare(MockResource::mock_id(), ctx).expect("alloc ok");
+        let (h2, ctx) = reg.prepare(MockResource::mock_id(), ctx).expect("alloc ok");
+        let g = Registry::borrow(&reg, h2).expect("acquire ok");
+        let _g2 = g.clone();
+
+        let entries = reg.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.contains(&(h1, EntryState::Inactive, 0)));
+        assert!(entries.contains(&(h2, EntryState::Active, 2)));
+        assert_eq!(reg.active().map(|(id, ..)| id).collect::<Vec<_>>(), [h2]);
+        assert_eq!(reg.inactive().map(|(id, ..)| id).collect::<Vec<_>>(), [h1]);
+
+        // Enumerating never blocks nor breaks the others.
+        let ths: Vec<_> = (0..THREAD_NUM)
+            .map(|_| {
+                let reg = reg.clone();
+                let mut cur_ctx = ctx.clone();
+                thread::spawn(move || {
+                    for _ in 0..ROUNDS {
+                        match reg.prepare(MockResource::mock_id(), cur_ctx) {
+                            Ok((h, ctx)) => cur_ctx = reg.clear(h, ctx),
+                            Err(ctx) => cur_ctx = ctx,
+                        }
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..ROUNDS {
+            let mut ids = reg.entries().map(|(id, ..)| id);
+            assert!(ids.all(|id| id.idx < N));
+            assert!(reg.active().any(|(id, ..)| id == h2));
+        }
+        let _: Vec<_> = ths.into_iter().map(|t| t.join().unwrap()).collect();
+
+        drop((g, _g2));
+        assert_eq!(reg.active().count(), 0);
+        assert_eq!(reg.inactive().count(), 2);
+        let ctx = reg.clear(h1, ctx);
+        let _ = reg.clear(h2, ctx);
+        assert_eq!(reg.entries().count(), 0);
+    }
+
     #[test]
     fn aba_impede() {
         const N: usize = 1;
@@ -584,11 +584,14 @@ fn stress_conn<H: Envelope, M: mem::Meta, F: FnMut(token::Token<M>) -> Option<to
     }
 }
 
-async fn client_conn<const N: usize, H: Envelope, M: mem::Meta>(
+async fn client_conn<H: Envelope, M: mem::Meta>(
     s: impl driver::Submitter<
-        driver::OwnOp<token::PackToken<H, M>, N>,
+        driver::OwnOp<token::PackToken<H, M>>,
         token::PackToken<H, M>,
-        Error = driver::TrySubmitError<channel::TrySendError<token::ReqToken<H, M>>>,
+        Error = driver::TrySubmitError<
+            channel::TrySendError<token::ReqToken<H, M>>,
+            token::PackToken<H, M>,
+        >,
     > + QueueChannel,
     mut f: impl FnMut() -> token::PackToken<H, M>,
     mut handler: impl FnMut(token::Token<M>),
@@ -611,14 +614,17 @@ async fn client_conn<const N: usize, H: Envelope, M: mem::Meta>(
                 continue;
             }
             Err(driver::TrySubmitError::SendError(channel::TrySendError::Disconnected)) => break,
-            Err(driver::TrySubmitError::CacheFull) => {
+            Err(driver::TrySubmitError::Aborted(_)) => break,
+            Err(driver::TrySubmitError::CacheFull(_)) => {
                 tokio::task::yield_now().await;
                 continue;
             }
         };
 
-        let (token, _) = op.await.unpack();
-        handler(token);
+        let Ok(token) = op.await else {
+            break;
+        };
+        handler(token.unpack().0);
     }
 }
 
@@ -627,7 +633,7 @@ async fn complete_conn<H: Envelope, M: mem::Meta>(
     + QueueChannel,
 ) {
     loop {
-        match completer.complete() {
+        match completer.complete_many(usize::MAX) {
             Ok(_) => continue,
             Err(channel::TryRecvError::Empty) => {
                 tokio::task::yield_now().await;
@@ -1,5 +1,5 @@
 use crate::msg::{Envelope, MoveMsg};
-use crate::perlude::arena::{MapAlloc, Optimistic,Session,SessionBy};
+use crate::perlude::arena::{MapAlloc, Optimistic, Session, SessionBy};
 
 use crate::tests::mock::{MAX_ADDR, MockAddr, MockBackend};
 use crate::tests::{self, prob, tracing_init};
@@ -71,8 +71,8 @@ fn conn_sync() {
     let h = conn.prepare(SIZE).expect("alloc ok");
     let q = conn.acquire(h).expect("view ok");
 
-    let (ls, lr) = q.clone().lsplit();
-    let (rs, rr) = q.clone().rsplit();
+    let (ls, lr) = q.clone().lsplit().expect("should attach");
+    let (rs, rr) = q.clone().rsplit().expect("should attach");
 
     let (msg, alloc) = MoveMsg::new(Info::mock(), alloc);
     let _ = ls.try_send(msg.with_default());
@@ -99,7 +99,7 @@ fn conn_sync() {
 
 #[tokio::test]
 async fn conn_async() {
-    use crate::perlude::arena::channel::{CachePool, ReqNull, Token};
+    use crate::perlude::arena::channel::{CachePool, Discard, ReqNull, Token};
     use crate::tests::Info;
 
     const N: usize = 1;
@@ -113,10 +113,10 @@ async fn conn_async() {
     let handle = conn.prepare(SIZE).expect("alloc ok");
     let view = conn.acquire(handle).expect("view ok");
 
-    let (ls, lr) = view.clone().lsplit();
-    let (rs, rr) = view.clone().rsplit();
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
 
-    let (ls, lr) = CachePool::<(), SIZE>::new().bind(ls, lr);
+    let (ls, lr) = CachePool::<()>::new(SIZE).bind(ls, lr, Discard);
 
     let alloc = conn.alloc.clone();
     let client_token = move || {
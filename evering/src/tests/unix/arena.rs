@@ -1,3 +1,4 @@
+use crate::boxed::PBox;
 use crate::msg::{Envelope, MoveMsg};
 use crate::os::FdBackend;
 use crate::os::unix::{AddrSpec, UnixFd};
@@ -80,10 +81,10 @@ async fn conn_async() {
     let handle = conn.prepare(QSIZE).expect("alloc ok");
     let view = conn.acquire(handle).expect("view ok");
 
-    let (ls, lr) = view.clone().lsplit();
-    let (rs, rr) = view.clone().rsplit();
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
 
-    let (ls, lr) = CachePool::<(), QSIZE>::new().bind(ls, lr);
+    let (ls, lr) = CachePool::<()>::new(QSIZE).bind(ls, lr, conn.alloc.clone());
 
     let alloc = conn.alloc.clone();
     let client_token = move || {
@@ -111,3 +112,1442 @@ async fn conn_async() {
 
     let _ = tokio::join!(server, client, completer);
 }
+
+#[tokio::test]
+async fn conn_park() {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::channel::{QueueChannel, RecvError};
+
+    const N: usize = 1;
+    const QSIZE: usize = 1;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "park";
+    const SIZE: usize = 60000;
+
+    tracing_init();
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+
+    let alloc = conn.alloc.clone();
+    let peer = thread::spawn(move || {
+        // wake the parked receiver
+        thread::sleep(DELAY);
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        rs.try_send(msg.with_default()).expect("should send");
+
+        // wake the parked sender
+        thread::sleep(DELAY);
+        let _ = rr.try_recv().expect("should receive");
+
+        // wake the parked receiver with disconnection
+        thread::sleep(DELAY);
+        rs.close();
+        rr
+    });
+
+    let token = lr.recv().await.expect("should receive");
+    let info = MoveMsg::<Info>::detoken(token.unpack().0, &conn.alloc).expect("should work");
+    tracing::debug!("[Park] receive: {:?}", info);
+
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    ls.try_send(msg.with_default()).expect("should send");
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    ls.send(msg.with_default()).await.expect("should send");
+
+    assert_eq!(lr.recv().await.err(), Some(RecvError));
+
+    let rr = peer.join().unwrap();
+    assert!(rr.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn conn_park_many() {
+    use std::thread;
+    use std::time::Duration;
+
+    const N: usize = 1;
+    const QSIZE: usize = 8;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "park_many";
+    const SIZE: usize = 60000;
+
+    /// Counts the threads of the process parking tasks.
+    fn parkers() -> usize {
+        std::fs::read_dir("/proc/self/task")
+            .expect("should list threads")
+            .filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("comm")).ok())
+            .filter(|comm| comm.trim() == "evering-park")
+            .count()
+    }
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (_ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, _rr) = view.clone().rsplit().expect("should attach");
+
+    let alloc = conn.alloc.clone();
+    let peer = thread::spawn(move || {
+        thread::sleep(DELAY);
+        // Every receiver is parked by now, on a single thread of the process.
+        let parked = parkers();
+        for _ in 0..QSIZE {
+            let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+            rs.try_send(msg.with_default()).expect("should send");
+        }
+        parked
+    });
+
+    let received = tokio::join!(
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+        lr.recv(),
+    );
+    for token in <[_; QSIZE]>::from(received) {
+        let (token, _) = token.expect("should receive").unpack();
+        MoveMsg::<Info>::detoken(token, &conn.alloc).expect("should work");
+    }
+    assert_eq!(peer.join().unwrap(), 1);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn conn_doorbell() {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::channel::{QueueChannel, RecvError};
+    use crate::perlude::arena::channel::DuplexBells;
+
+    const N: usize = 1;
+    const QSIZE: usize = 4;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "doorbell";
+    const SIZE: usize = 60000;
+
+    tracing_init();
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let bells = DuplexBells::new().expect("should create");
+    let (ls, lr) = bells.lsplit(view.clone()).expect("should register");
+    let (rs, rr) = bells.rsplit(view.clone()).expect("should register");
+
+    let alloc = conn.alloc.clone();
+    let peer = thread::spawn(move || {
+        // ring the sleeping receiver
+        thread::sleep(DELAY);
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        rs.try_send(msg.with_default()).expect("should send");
+
+        // ring the sleeping receiver with disconnection
+        thread::sleep(DELAY);
+        rs.close();
+    });
+
+    let token = lr.recv().await.expect("should receive");
+    let info = MoveMsg::<Info>::detoken(token.unpack().0, &conn.alloc).expect("should work");
+    tracing::debug!("[Doorbell] receive: {:?}", info);
+
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    ls.try_send(msg.with_default()).expect("should send");
+    assert!(rr.recv().await.is_ok());
+
+    assert_eq!(lr.recv().await.err(), Some(RecvError));
+    peer.join().unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn conn_doorbell_close() {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::channel::RecvError;
+    use crate::perlude::arena::channel::DuplexBells;
+
+    const N: usize = 1;
+    const QSIZE: usize = 4;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "doorbell_close";
+    const SIZE: usize = 60000;
+
+    tracing_init();
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let bells = DuplexBells::new().expect("should create");
+    let (_ls, lr) = bells.lsplit(view.clone()).expect("should register");
+    let (rs, _rr) = bells.rsplit(view.clone()).expect("should register");
+
+    // the last sender is a plain queue sender which never rings
+    let (tx, _bell) = rs.into_inner();
+    let peer = thread::spawn(move || {
+        thread::sleep(DELAY);
+        drop(tx);
+    });
+
+    assert_eq!(lr.recv().await.err(), Some(RecvError));
+    peer.join().unwrap();
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn conn_drive() {
+    use crate::channel::driver::{Identifier, OpError, Submitter};
+    use crate::perlude::arena::channel::{CachePool, DuplexBells, MsgToken, ReqNull, Token};
+
+    const QSIZE: usize = 4;
+    const OPS: usize = 16;
+
+    const NAME: &str = "drive";
+    const SIZE: usize = 60000;
+
+    tracing_init();
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let bells = DuplexBells::new().expect("should create");
+    let (ls, lr) = bells.lsplit(view.clone()).expect("should register");
+    let (rs, rr) = bells.rsplit(view.clone()).expect("should register");
+    let (sx, cx) = CachePool::<()>::new(QSIZE).bind(ls, lr, conn.alloc.clone());
+    let reactor = tokio::spawn(cx.drive());
+
+    let alloc = conn.alloc.clone();
+    let server = tokio::spawn(async move {
+        for _ in 0..OPS {
+            let item = rr.recv().await.expect("should receive");
+            let (req, id): (MsgToken<()>, _) = item.decompose();
+            MoveMsg::<Info>::detoken(req.unpack().0, &alloc).expect("should work");
+            rs.try_send(Identifier::compose(Token::null().with_default(), id))
+                .expect("should send");
+        }
+        // The last request is left unanswered.
+        let item = rr.recv().await.expect("should receive");
+        let (req, _): (MsgToken<()>, _) = item.decompose();
+        MoveMsg::<Info>::detoken(req.unpack().0, &alloc).expect("should work");
+        // Dropping the last sender rings the reactor with the disconnection.
+    });
+
+    for _ in 0..OPS {
+        let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+        let op = sx.try_submit(msg.with_default()).expect("should submit");
+        op.await.expect("should complete");
+    }
+
+    // The op pending once the peer is gone fails rather than hanging.
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    let op = sx.try_submit(msg.with_default()).expect("should submit");
+    assert!(matches!(op.await, Err(OpError::Disconnected)));
+
+    server.await.unwrap();
+    assert_eq!(reactor.await.unwrap(), OPS);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn conn_submit() {
+    use std::sync::Arc;
+
+    use crate::channel::driver::Identifier;
+    use crate::perlude::arena::channel::{CachePool, DuplexBells, MsgToken, ReqNull, Token};
+
+    // Fewer slots in the queue than entries in the pool, and fewer entries than submitters.
+    const QSIZE: usize = 2;
+    const CAP: usize = 4;
+    const TASKS: usize = 8;
+    const OPS: usize = 8;
+
+    const NAME: &str = "submit";
+    const SIZE: usize = 60000;
+
+    tracing_init();
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let bells = DuplexBells::new().expect("should create");
+    let (ls, lr) = bells.lsplit(view.clone()).expect("should register");
+    let (rs, rr) = bells.rsplit(view.clone()).expect("should register");
+    let (sx, cx) = CachePool::<()>::new(CAP).bind(ls, lr, conn.alloc.clone());
+    let reactor = tokio::spawn(cx.drive());
+
+    let alloc = conn.alloc.clone();
+    let server = tokio::spawn(async move {
+        for _ in 0..TASKS * OPS {
+            let item = rr.recv().await.expect("should receive");
+            let (req, id): (MsgToken<()>, _) = item.decompose();
+            MoveMsg::<Info>::detoken(req.unpack().0, &alloc).expect("should work");
+            rs.send(Identifier::compose(Token::null().with_default(), id))
+                .await
+                .expect("should send");
+            // Let the queue fill up.
+            tokio::task::yield_now().await;
+        }
+    });
+
+    let sx = Arc::new(sx);
+    let clients = (0..TASKS)
+        .map(|_| {
+            let sx = sx.clone();
+            let alloc = conn.alloc.clone();
+            tokio::spawn(async move {
+                for _ in 0..OPS {
+                    let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+                    let op = sx.submit(msg.with_default()).await.expect("should submit");
+                    op.await.expect("should complete");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for client in clients {
+        client.await.unwrap();
+    }
+
+    server.await.unwrap();
+    assert_eq!(reactor.await.unwrap(), TASKS * OPS);
+}
+
+#[test]
+fn conn_disconnect() {
+    use crate::channel::{QueueChannel, TryRecvError};
+    use crate::perlude::arena::EntryState;
+
+    const N: usize = 1;
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "disconnect";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+    // The duplex is found back while it is in use.
+    assert!(matches!(
+        conn.entries().collect::<Vec<_>>()[..],
+        [(id, EntryState::Active, 1)] if id == handle
+    ));
+    assert_eq!(conn.active().count(), 1);
+    assert_eq!(conn.inactive().count(), 0);
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+
+    // The direction is closed once its last sender drops.
+    let rs2 = rs.clone();
+    drop(rs);
+    assert!(matches!(lr.try_recv(), Err(TryRecvError::Empty)));
+    drop(rs2);
+    assert!(matches!(lr.try_recv(), Err(TryRecvError::Disconnected)));
+
+    // The direction is closed once its last receiver drops.
+    assert!(!ls.probe());
+    drop(rr);
+    assert!(ls.is_close());
+}
+
+#[test]
+fn conn_blocking() {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::channel::{QueueChannel, RecvError, RecvTimeoutError, SendTimeoutError};
+
+    const N: usize = 1;
+    const QSIZE: usize = 1;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "blocking";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+
+    assert!(matches!(
+        lr.recv_timeout(DELAY),
+        Err(RecvTimeoutError::Timeout)
+    ));
+
+    let alloc = conn.alloc.clone();
+    let peer = thread::spawn(move || {
+        // wake the blocked receiver
+        thread::sleep(DELAY);
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        rs.try_send(msg.with_default()).expect("should send");
+
+        // wake the blocked sender
+        thread::sleep(DELAY);
+        let _ = rr.recv_blocking().expect("should receive");
+
+        // wake the blocked receiver with disconnection
+        thread::sleep(DELAY);
+        rs.close();
+        rr
+    });
+
+    let token = lr.recv_blocking().expect("should receive");
+    let info = MoveMsg::<Info>::detoken(token.unpack().0, &conn.alloc).expect("should work");
+    tracing::debug!("[Blocking] receive: {:?}", info);
+
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    ls.try_send(msg.with_default()).expect("should send");
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    ls.send_blocking(msg.with_default()).expect("should send");
+
+    // The queue stays full, the item is handed back.
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    let Err(SendTimeoutError::Timeout(token)) = ls.send_timeout(msg.with_default(), DELAY) else {
+        panic!("should time out")
+    };
+    MoveMsg::<Info>::detoken(token.unpack().0, &conn.alloc).expect("should work");
+
+    assert_eq!(lr.recv_blocking().err(), Some(RecvError));
+
+    let rr = peer.join().unwrap();
+    assert!(rr.try_recv().is_ok());
+}
+
+#[test]
+fn conn_select() {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::channel::select::Poller;
+
+    const N: usize = 3;
+    const QSIZE: usize = 4;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    const NAME: &str = "select";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<(), N>(NAME, SIZE);
+    let views = (0..N)
+        .map(|_| {
+            let handle = conn.prepare(QSIZE).expect("alloc ok");
+            conn.acquire(handle).expect("view ok")
+        })
+        .collect::<Vec<_>>();
+    let rxs = views
+        .iter()
+        .map(|view| view.clone().lsplit().expect("should attach").1)
+        .collect::<Vec<_>>();
+    let txs = views
+        .iter()
+        .map(|view| view.clone().rsplit().expect("should attach").0)
+        .collect::<Vec<_>>();
+
+    let mut poller = Poller::new();
+    let keys = rxs.iter().map(|rx| poller.register(rx)).collect::<Vec<_>>();
+    assert_eq!(poller.len(), N);
+    assert_eq!(poller.try_select(), None);
+    assert_eq!(poller.select_timeout(DELAY), None);
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(DELAY);
+            let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+            txs[1].try_send(msg.with_default()).expect("should send");
+        });
+        assert_eq!(poller.select(), keys[1]);
+    });
+
+    // An always ready receiver must not starve the others.
+    let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+    txs[0].try_send(msg.with_default()).expect("should send");
+    let picks = (0..4)
+        .map(|_| poller.try_select().expect("should be ready"))
+        .collect::<Vec<_>>();
+    assert_eq!(picks, [keys[0], keys[1], keys[0], keys[1]]);
+
+    let mut ready = Vec::new();
+    assert_eq!(poller.ready(&mut ready), 2);
+    ready.sort();
+    assert_eq!(ready, [keys[0], keys[1]]);
+
+    assert!(poller.deregister(keys[0]).is_some());
+    assert_eq!(poller.try_select(), Some(keys[1]));
+
+    for (key, rx) in keys.iter().zip(&rxs) {
+        while let Ok(token) = rx.try_recv() {
+            tracing::debug!("[Select] drain {}", key);
+            MoveMsg::<Info>::detoken(token.unpack().0, &conn.alloc).expect("should work");
+        }
+    }
+}
+
+#[cfg(feature = "resize")]
+#[test]
+fn conn_resize() {
+    use crate::channel::cross::ResizeError;
+    use crate::channel::{QueueChannel, TrySendError};
+    use std::time::Duration;
+
+    const QSIZE: usize = 4;
+    const NAME: &str = "resize";
+    const SIZE: usize = 60000;
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    let conn = mock_session::<(), 2>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+    let tx = view.clone().lsplit().expect("should attach").0;
+    let rx = view.clone().rsplit().expect("should attach").1;
+    let alloc = conn.alloc.clone();
+
+    let send = |i: u32| {
+        let info = Info {
+            version: i,
+            data: 0,
+        };
+        let (msg, _) = MoveMsg::new(info, &alloc);
+        tx.try_send(msg.with_default()).map_err(|e| {
+            // A rejected message is freed rather than leaked.
+            if let TrySendError::Full(token) = e {
+                token.unpack().0.discard(&alloc);
+            }
+        })
+    };
+    let recv = || {
+        let token = rx.try_recv().ok()?;
+        let info = MoveMsg::<Info>::detoken(token.unpack().0, &alloc).expect("should work");
+        Some(info.version)
+    };
+
+    for i in 0..QSIZE as u32 {
+        send(i).expect("should send");
+    }
+    assert!(send(QSIZE as u32).is_err());
+    assert_eq!(recv(), Some(0));
+
+    // The pending items wrap around the old buffer, they must come out in order.
+    send(QSIZE as u32).expect("should send");
+    view.resize(QSIZE * 2, &conn.alloc, TIMEOUT)
+        .expect("should grow");
+    for i in QSIZE + 1..QSIZE * 2 + 1 {
+        send(i as u32).expect("should send");
+    }
+    assert!(send(0).is_err());
+
+    assert_eq!(
+        view.resize(QSIZE, &conn.alloc, TIMEOUT),
+        Err(ResizeError::Overflow(QSIZE * 2))
+    );
+    for i in 1..QSIZE * 2 + 1 {
+        assert_eq!(recv(), Some(i as u32));
+    }
+    assert_eq!(recv(), None);
+
+    view.resize(1, &conn.alloc, TIMEOUT).expect("should shrink");
+    send(7).expect("should send");
+    assert!(send(8).is_err());
+    assert_eq!(recv(), Some(7));
+
+    // A direction whose items do not fit leaves the other one untouched.
+    let handle = conn.prepare(2).expect("alloc ok");
+    let duplex = conn.acquire(handle).expect("view ok");
+    let (ltx, rrx) = duplex.clone().lsplit().expect("should attach");
+    let (rtx, _lrx) = duplex.clone().rsplit().expect("should attach");
+    for i in 0..2 {
+        let info = Info {
+            version: i,
+            data: 0,
+        };
+        let (msg, _) = MoveMsg::new(info, &alloc);
+        rtx.try_send(msg.with_default()).expect("should send");
+    }
+    assert_eq!(
+        duplex.resize(1, &conn.alloc, TIMEOUT),
+        Err(ResizeError::Overflow(2))
+    );
+    assert_eq!(ltx.capacity(), 2);
+    while let Ok(token) = rrx.try_recv() {
+        token.unpack().0.discard(&alloc);
+    }
+
+    // An operation in progress past the deadline fails the resize, and lets the queue resume.
+    let mut ltx = ltx;
+    let slot = ltx.reserve().expect("should reserve");
+    assert_eq!(
+        duplex.resize(4, &conn.alloc, Duration::from_millis(10)),
+        Err(ResizeError::Timeout)
+    );
+    drop(slot);
+    duplex.resize(4, &conn.alloc, TIMEOUT).expect("should grow");
+    assert_eq!(ltx.capacity(), 4);
+}
+
+#[tokio::test]
+async fn conn_cancel() {
+    use crate::channel::driver::{CancelSet, Completer, Identifier, Submitter, TryCompState};
+    use crate::perlude::arena::channel::{CachePool, MsgToken, ReqNull, Token};
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "cancel";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<()>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+    let cancels = CancelSet::new(QSIZE);
+
+    let alloc = conn.alloc.clone();
+    let request = || {
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        msg.with_default()
+    };
+    let accept = || {
+        let item = rr.try_recv().expect("should receive");
+        CancelSet::accept::<_, MsgToken<()>, _>(&cancels, item)
+    };
+    let reply = |req: MsgToken<()>, id| {
+        let (token, _) = req.unpack();
+        MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+        rs.try_send(Identifier::compose(Token::null().with_default(), id))
+            .expect("should send");
+    };
+
+    // A completed op tells the server nothing.
+    let op = sx.try_submit(request()).expect("should submit");
+    let (req, token) = accept().expect("should be a request");
+    reply(req, token.id());
+    assert_eq!(cx.complete().unwrap(), TryCompState::Prefilled);
+    op.await.expect("should complete");
+    assert_eq!(sx.flush_cancel(), 0);
+    assert!(!token.is_cancelled());
+
+    let op = sx.try_submit(request()).expect("should submit");
+    let (req, token) = accept().expect("should be a request");
+    op.cancel();
+    // The server is told right away.
+    assert_eq!(pool.len(), 0);
+    assert_eq!(sx.flush_cancel(), 0);
+    assert!(!token.is_cancelled());
+    assert!(accept().is_none());
+    assert!(token.is_cancelled());
+
+    // The late response is outdated.
+    reply(req, token.id());
+    assert_eq!(cx.complete().unwrap(), TryCompState::Outdated);
+
+    // On a full queue, the cancellation goes out ahead of the next request instead.
+    let op = sx.try_submit(request()).expect("should submit");
+    let (req, token) = accept().expect("should be a request");
+    MoveMsg::<Info>::detoken(req.unpack().0, &alloc).expect("should work");
+    let filler = view.clone().lsplit().expect("should attach").0;
+    while filler
+        .try_send(Identifier::compose(
+            Token::null().with_default(),
+            token.id(),
+        ))
+        .is_ok()
+    {}
+    drop(op);
+    // The entry is held until the server is told.
+    assert_eq!(pool.len(), 1);
+    for _ in 0..QSIZE {
+        rr.try_recv().expect("should receive");
+    }
+    let _op = sx.try_submit(request()).expect("should submit");
+    assert!(accept().is_none());
+    assert!(token.is_cancelled());
+    let (_, token) = accept().expect("should be a request");
+    assert!(!token.is_cancelled());
+}
+
+#[tokio::test]
+async fn conn_timeout() {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use std::pin::pin;
+    use std::task::{Context, Waker};
+    use std::time::Duration;
+
+    use crate::channel::driver::{
+        CancelSet, Completer, Identifier, OpError, Submitter, Timer, TryCompState,
+    };
+    use crate::perlude::arena::channel::{CachePool, MsgToken, ReqNull};
+
+    const QSIZE: usize = 4;
+    const DELAY: Duration = Duration::from_millis(20);
+
+    struct Clock;
+
+    impl Timer for Clock {
+        type Instant = tokio::time::Instant;
+        type Sleep = tokio::time::Sleep;
+
+        fn deadline(&self, timeout: Duration) -> Self::Instant {
+            tokio::time::Instant::now() + timeout
+        }
+
+        fn sleep_until(&self, deadline: Self::Instant) -> Self::Sleep {
+            tokio::time::sleep_until(deadline)
+        }
+    }
+
+    const NAME: &str = "timeout";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<()>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+    let cancels = CancelSet::new(QSIZE);
+
+    let alloc = conn.alloc.clone();
+    let request = || {
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        msg.with_default()
+    };
+    // Echoes the request back.
+    let serve = || {
+        let item = rr.try_recv().expect("should receive");
+        let (req, token) = CancelSet::accept::<_, MsgToken<()>, _>(&cancels, item)?;
+        rs.try_send(Identifier::compose(req, token.id()))
+            .expect("should send");
+        Some(())
+    };
+
+    let op = sx.try_submit(request()).expect("should submit");
+    assert!(matches!(
+        op.timeout(&Clock, DELAY).await,
+        Err(OpError::Timeout)
+    ));
+    // The entry is reclaimed, the server is told right away.
+    assert_eq!(sx.flush_cancel(), 0);
+    assert_eq!(pool.len(), 0);
+
+    // The late response is handed back to be freed.
+    serve().expect("should be a request");
+    let mut late = None;
+    let state = cx
+        .complete_with(|payload| late = Some(payload))
+        .expect("should complete");
+    assert_eq!(state, TryCompState::Outdated);
+    let (token, _) = late.expect("should be outdated").unpack();
+    MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+    assert!(serve().is_none());
+
+    let mut op = pin!(
+        sx.try_submit_with_deadline(request(), &Clock, Clock.deadline(DELAY))
+            .expect("should submit")
+    );
+    serve().expect("should be a request");
+    cx.complete().expect("should complete");
+    let (token, _) = op.as_mut().await.expect("should complete").unpack();
+    MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+    assert_eq!(pool.len(), 0);
+
+    // A resolved op is never polled again.
+    let mut cx = Context::from_waker(Waker::noop());
+    assert!(catch_unwind(AssertUnwindSafe(|| op.as_mut().poll(&mut cx))).is_err());
+}
+
+#[tokio::test]
+async fn conn_abort() {
+    use crate::channel::TryRecvError;
+    use crate::channel::driver::{Completer, OpError, Submitter, TrySubmitError};
+    use crate::perlude::arena::channel::{CachePool, ReqNull};
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "abort";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, _rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<()>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    let request = || {
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        msg.with_default()
+    };
+
+    let polled = tokio::spawn(sx.try_submit(request()).expect("should submit"));
+    let idle = sx.try_submit(request()).expect("should submit");
+    // Let the first op register its waker.
+    tokio::task::yield_now().await;
+
+    // The server is gone, the pending ops fail.
+    drop(rs);
+    assert!(matches!(cx.complete(), Err(TryRecvError::Disconnected)));
+    assert_eq!(pool.aborted(), Some(OpError::Disconnected));
+    assert!(matches!(polled.await.unwrap(), Err(OpError::Disconnected)));
+    assert!(matches!(idle.await, Err(OpError::Disconnected)));
+    assert_eq!(pool.len(), 0);
+
+    // No op is claimed from now on.
+    let Err(TrySubmitError::Aborted(item)) = sx.try_submit(request()) else {
+        panic!("should be aborted");
+    };
+    MoveMsg::<Info>::detoken(item.unpack().0, &alloc).expect("should work");
+    assert_eq!(pool.len(), 0);
+}
+
+#[tokio::test]
+async fn conn_dead_letter() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::channel::driver::{
+        CancelSet, Completer, DeadLetter, Identifier, Submitter, TryCompState, TrySubmitError,
+    };
+    use crate::perlude::arena::channel::{CachePool, MsgToken, ReqNull, Token};
+
+    const QSIZE: usize = 2;
+
+    const NAME: &str = "dead_letter";
+    const SIZE: usize = 60000;
+
+    /// Frees the payloads through the allocator, counting them.
+    struct Counted(UnixAlloc, Arc<AtomicUsize>);
+
+    impl DeadLetter<MsgToken<()>> for Counted {
+        fn dispose(&self, payload: MsgToken<()>) {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            self.0.dispose(payload);
+        }
+    }
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let dead = Arc::new(AtomicUsize::new(0));
+    let (sx, cx) =
+        CachePool::<()>::new(QSIZE).bind(ls, lr, Counted(conn.alloc.clone(), dead.clone()));
+    let cancels = CancelSet::new(QSIZE);
+    let disposed = || dead.load(Ordering::Relaxed);
+
+    let alloc = conn.alloc.clone();
+    let request = || {
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        msg.with_default()
+    };
+    // Echoes the next request back.
+    let serve = || {
+        loop {
+            let item = rr.try_recv().expect("should receive");
+            if let Some((req, token)) = CancelSet::accept::<_, MsgToken<()>, _>(&cancels, item) {
+                rs.try_send(Identifier::compose(req, token.id()))
+                    .expect("should send");
+                break;
+            }
+        }
+    };
+
+    // A late response is disposed once its op is gone.
+    drop(sx.try_submit(request()).expect("should submit"));
+    serve();
+    assert_eq!(
+        cx.complete().expect("should complete"),
+        TryCompState::Outdated
+    );
+    assert_eq!(disposed(), 1);
+
+    // So is a response never claimed.
+    let op = sx.try_submit(request()).expect("should submit");
+    serve();
+    assert_eq!(
+        cx.complete().expect("should complete"),
+        TryCompState::Prefilled
+    );
+    assert_eq!(disposed(), 1);
+    drop(op);
+    assert_eq!(disposed(), 2);
+
+    // A request finding the pool full is handed back.
+    let ops = (0..QSIZE)
+        .map(|_| sx.try_submit(request()).expect("should submit"))
+        .collect::<Vec<_>>();
+    let Err(TrySubmitError::CacheFull(item)) = sx.try_submit(request()) else {
+        panic!("should be full");
+    };
+    let (token, _) = item.unpack();
+    MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+    drop(ops);
+    (0..QSIZE).for_each(|_| serve());
+    assert_eq!(cx.complete_many(QSIZE).expect("should complete"), QSIZE);
+    assert_eq!(disposed(), 2 + QSIZE);
+
+    // So is a request whose submission is dropped while the queue is full.
+    let filler = view.clone().lsplit().expect("should attach").0;
+    let id = crate::numeric::Id { idx: 0, live: 0 };
+    while filler
+        .try_send(Identifier::compose(Token::null().with(()), id))
+        .is_ok()
+    {}
+    {
+        let submit = core::pin::pin!(sx.submit(request()));
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        assert!(submit.poll(&mut cx).is_pending());
+    }
+    assert_eq!(disposed(), 3 + QSIZE);
+}
+
+#[tokio::test]
+async fn conn_chain() {
+    use crate::channel::driver::{Completer, Identifier, OpError};
+    use crate::perlude::arena::channel::{CachePool, Links, MsgToken, ReqNull, Token, serve_chain};
+
+    const QSIZE: usize = 8;
+    const FAIL: u32 = u32::MAX;
+
+    const NAME: &str = "chain";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<()>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    let request = |version| {
+        let info = Info {
+            version,
+            ..Info::mock()
+        };
+        let (msg, _) = MoveMsg::new(info, &alloc);
+        msg.with_default()
+    };
+    // Echoes the request back, failing the marked ones.
+    let echo = async |request: MsgToken<()>| {
+        let (token, header) = request.unpack();
+        let info = MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+        let failed = info.version == FAIL;
+        let (msg, _) = MoveMsg::new(*info, &alloc);
+        let response = msg.with(header);
+        if failed { Err(response) } else { Ok(response) }
+    };
+    // The skipped links are cancelled.
+    let unskippable = |_| unreachable!("should cancel");
+
+    let broken = sx
+        .chain()
+        .link(request(0))
+        .link(request(FAIL))
+        .link(request(0))
+        .submit()
+        .await
+        .expect("should submit");
+    let whole = sx
+        .chain()
+        .link(request(1))
+        .link(request(1))
+        .submit()
+        .await
+        .expect("should submit");
+    assert_eq!(pool.len(), 5);
+
+    // The chains are found back whatever arrives in between.
+    let mut items = (0..5)
+        .map(|_| Some(rr.try_recv().expect("should receive")))
+        .collect::<Vec<_>>();
+    let mut links = Links::<MsgToken<()>>::new();
+    for i in [0, 3, 1, 4] {
+        let item = items[i].take().unwrap();
+        let chain = links.push(item);
+        assert_eq!(chain.is_some(), i == 4);
+        if let Some(chain) = chain {
+            assert_eq!(chain.len(), 2);
+            let skipped = serve_chain(&rs, chain, echo, unskippable)
+                .await
+                .expect("should send");
+            assert!(skipped.is_empty());
+        }
+    }
+    let chain = links.push(items[2].take().unwrap());
+    assert!(links.is_empty());
+
+    // The link after the failing one is skipped.
+    let chain = chain.expect("should be complete");
+    assert_eq!(chain.len(), 3);
+    let skipped = serve_chain(&rs, chain, echo, unskippable)
+        .await
+        .expect("should send");
+    assert_eq!(skipped.len(), 1);
+    for request in skipped {
+        MoveMsg::<Info>::detoken(request.unpack().0, &alloc).expect("should work");
+    }
+
+    assert_eq!(cx.complete_many(5).expect("should complete"), 5);
+    let results = whole.await;
+    assert!(results.iter().all(Result::is_ok));
+    let mut results = broken.await.into_iter();
+    for version in [0, FAIL] {
+        let (token, _) = results.next().unwrap().expect("should complete").unpack();
+        let echo = MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+        assert_eq!(echo.version, version);
+    }
+    assert!(matches!(results.next(), Some(Err(OpError::Cancelled))));
+    assert_eq!(pool.len(), 0);
+
+    // A chain whose submission is dropped midway, here with room for its first link only.
+    let filler = view.clone().lsplit().expect("should attach").0;
+    let id = crate::numeric::Id { idx: 0, live: 0 };
+    while filler
+        .try_send(Identifier::compose(Token::null().with(()), id))
+        .is_ok()
+    {}
+    rr.try_recv().expect("should receive");
+    {
+        let submit = core::pin::pin!(sx.chain().link(request(2)).link(request(2)).submit());
+        let mut cx = core::task::Context::from_waker(core::task::Waker::noop());
+        assert!(submit.poll(&mut cx).is_pending());
+    }
+    for _ in 1..QSIZE {
+        rr.try_recv().expect("should receive");
+    }
+    let link = rr.try_recv().expect("should receive");
+    assert!(links.push(link).is_none());
+    // Its first link is cancelled as its op drops, which aborts the chain.
+    assert_eq!(sx.flush_cancel(), 1);
+    let cancel = rr.try_recv().expect("should receive");
+    let id = Identifier::<MsgToken<()>>::cancelled(&cancel).expect("should be a cancel");
+    let aborted = links.abort(id).expect("should abort");
+    assert!(links.is_empty());
+    for (request, _) in aborted {
+        MoveMsg::<Info>::detoken(request.unpack().0, &alloc).expect("should work");
+    }
+    assert_eq!(pool.len(), 0);
+}
+
+#[tokio::test]
+async fn conn_chain_skip() {
+    use crate::channel::driver::Completer;
+    use crate::perlude::arena::channel::{CachePool, Links, MsgToken, serve_chain};
+    use crate::token::ReqId;
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "chain_skip";
+    const SIZE: usize = 60000;
+
+    /// An envelope carrying no cancel message.
+    #[derive(Clone, Copy, Debug, Default)]
+    struct Bare;
+
+    impl Envelope for Bare {}
+
+    let conn = mock_session::<ReqId<Bare>, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<Bare>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    let request = || MoveMsg::new(Info::mock(), &alloc).0.with_default();
+    let chain = sx
+        .chain()
+        .link(request())
+        .link(request())
+        .submit()
+        .await
+        .expect("should submit");
+
+    let mut links = Links::<MsgToken<Bare>>::new();
+    assert!(links.push(rr.try_recv().expect("should receive")).is_none());
+    let served = links
+        .push(rr.try_recv().expect("should receive"))
+        .expect("should be complete");
+    // The first link fails, the second one is answered by `skip` for want of a cancel message.
+    let skipped = serve_chain(
+        &rs,
+        served,
+        async |request: MsgToken<Bare>| Err(request),
+        |request: MsgToken<Bare>| request,
+    )
+    .await
+    .expect("should send");
+    assert!(skipped.is_empty());
+
+    assert_eq!(cx.complete_many(2).expect("should complete"), 2);
+    for result in chain.await {
+        let (token, _) = result.expect("should complete").unpack();
+        MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+    }
+    assert_eq!(pool.len(), 0);
+}
+
+#[tokio::test]
+async fn conn_serve() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::channel::driver::{DeadLetter, Submitter};
+    use crate::perlude::arena::channel::{CachePool, MsgToken, ReqNull, Service, serve};
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "serve";
+    const SIZE: usize = 60000;
+
+    struct Echo;
+
+    impl Service<UnixAlloc> for Echo {
+        type Request = MsgToken<()>;
+        type Response = MsgToken<()>;
+
+        async fn call(&self, request: MsgToken<()>, alloc: &UnixAlloc) -> MsgToken<()> {
+            let (token, header) = request.unpack();
+            let info = MoveMsg::<Info>::detoken(token, alloc).expect("should work");
+            // Let the other requests in.
+            tokio::task::yield_now().await;
+            let (msg, _) = MoveMsg::new(*info, alloc);
+            msg.with(header)
+        }
+
+        fn concurrency(&self) -> usize {
+            2
+        }
+    }
+
+    /// Frees the payloads through the allocator, counting them.
+    struct Counted(UnixAlloc, Arc<AtomicUsize>);
+
+    impl DeadLetter<MsgToken<()>> for Counted {
+        fn dispose(&self, payload: MsgToken<()>) {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            self.0.dispose(payload);
+        }
+    }
+
+    impl Service<Counted> for Echo {
+        type Request = MsgToken<()>;
+        type Response = MsgToken<()>;
+
+        async fn call(&self, request: MsgToken<()>, alloc: &Counted) -> MsgToken<()> {
+            Service::<UnixAlloc>::call(self, request, &alloc.0).await
+        }
+
+        fn concurrency(&self) -> usize {
+            Service::<UnixAlloc>::concurrency(self)
+        }
+    }
+
+    let conn = mock_session::<ReqNull, 2>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let (sx, cx) = CachePool::<()>::new(QSIZE).bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    let client = async move {
+        let mut ops = Vec::new();
+        for _ in 0..QSIZE {
+            let info = Info::mock();
+            let (msg, _) = MoveMsg::new(info, &alloc);
+            let op = sx.try_submit(msg.with_default()).expect("should submit");
+            ops.push((op, info));
+        }
+        for (op, info) in ops {
+            let (token, _) = op.await.expect("should complete").unpack();
+            let echo = MoveMsg::<Info>::detoken(token, &alloc).expect("should work");
+            assert_eq!(echo.data, info.data);
+        }
+        // Dropping the submitter closes the requests, which stops the server.
+    };
+
+    let (served, completed, ()) =
+        tokio::join!(serve(rr, rs, conn.alloc.clone(), Echo), cx.drive(), client);
+    assert_eq!(served, QSIZE);
+    assert_eq!(completed, QSIZE);
+
+    // Once the client is gone, the responses of the requests in flight are disposed of.
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let (sx, cx) = CachePool::<()>::new(QSIZE).bind(ls, lr, conn.alloc.clone());
+    let ops = (0..QSIZE)
+        .map(|_| {
+            let (msg, _) = MoveMsg::new(Info::mock(), &conn.alloc);
+            sx.try_submit(msg.with_default()).expect("should submit")
+        })
+        .collect::<Vec<_>>();
+    drop(cx);
+
+    let dead = Arc::new(AtomicUsize::new(0));
+    let counted = Counted(conn.alloc.clone(), dead.clone());
+    assert_eq!(serve(rr, rs, counted, Echo).await, 0);
+    // The unsent response, and the one of the other request in flight.
+    assert_eq!(dead.load(Ordering::Relaxed), 2);
+    drop(ops);
+}
+
+#[tokio::test]
+async fn conn_stream() {
+    use crate::channel::driver::{CancelSet, Identifier};
+    use crate::perlude::arena::channel::{CachePool, MsgToken, ReqNull};
+
+    const QSIZE: usize = 4;
+    const WINDOW: u32 = 2;
+    const CHUNKS: u32 = 8;
+
+    const NAME: &str = "stream";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let pool = CachePool::<()>::new(QSIZE);
+    let (sx, cx) = pool.clone().bind(ls, lr, conn.alloc.clone());
+    let set = CancelSet::new(QSIZE);
+
+    let alloc = conn.alloc.clone();
+    let server = async move {
+        let accept = async || {
+            let item = rr.recv().await.expect("should receive");
+            CancelSet::accept::<_, MsgToken<()>, _>(&set, item)
+        };
+        let (req, token) = accept().await.expect("should be a request");
+        MoveMsg::<Info>::detoken(req.unpack().0, &alloc).expect("should work");
+
+        for i in 0..CHUNKS {
+            // The credits come along with the requests.
+            while !token.try_acquire() {
+                assert!(accept().await.is_none());
+            }
+            let (msg, _) = MoveMsg::new(
+                Info {
+                    version: 0,
+                    data: i,
+                },
+                &alloc,
+            );
+            let msg = msg.with_default();
+            let chunk = if i + 1 < CHUNKS {
+                Identifier::compose_more(msg, token.id())
+            } else {
+                Identifier::compose(msg, token.id())
+            };
+            rs.send(chunk).await.expect("should send");
+        }
+    };
+
+    let alloc = conn.alloc.clone();
+    let client = async move {
+        let (msg, _) = MoveMsg::new(Info::mock(), &alloc);
+        let mut stream = sx
+            .try_submit_stream(msg.with_default(), WINDOW)
+            .expect("should submit");
+        let mut n = 0;
+        while let Some(chunk) = stream.next_item().await {
+            let info = MoveMsg::<Info>::detoken(chunk.unpack().0, &alloc).expect("should work");
+            assert_eq!(info.data, n);
+            n += 1;
+        }
+        assert_eq!(n, CHUNKS);
+        assert!(stream.next_item().await.is_none());
+        // A whole stream holds a single entry.
+        assert_eq!(pool.len(), 1);
+        drop(stream);
+        assert_eq!(pool.len(), 0);
+    };
+
+    let (completed, (), ()) = tokio::join!(cx.drive(), server, client);
+    assert_eq!(completed, CHUNKS as usize);
+}
+
+#[tokio::test]
+async fn conn_service() {
+    use crate::msg::TypeTag;
+    use crate::perlude::arena::channel::{CachePool, CallError, Method, ReqId, serve};
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "service";
+    const SIZE: usize = 60000;
+
+    crate::service! {
+        in crate::perlude::arena;
+        trait Calc {
+            fn bump(Info) -> Info;
+            fn sum(Info) -> u32;
+        }
+    }
+
+    crate::service! {
+        in crate::perlude::arena;
+        trait Other {
+            fn bump(Info) -> Info;
+        }
+    }
+
+    // A service of the same name in another module.
+    mod shadow {
+        use crate::tests::Info;
+
+        crate::service! {
+            in crate::perlude::arena;
+            pub(crate) trait Calc {
+                fn bump(Info) -> Info;
+            }
+        }
+    }
+
+    struct Impl;
+
+    impl Calc<UnixAlloc> for Impl {
+        async fn bump(&self, request: PBox<Info, UnixAlloc>) -> Info {
+            Info {
+                version: request.version + 1,
+                ..*request
+            }
+        }
+
+        async fn sum(&self, request: PBox<Info, UnixAlloc>) -> u32 {
+            request.version + request.data
+        }
+    }
+
+    let conn = mock_session::<ReqId<Method>, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let (sx, cx) = CachePool::new(QSIZE).bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    let client = async move {
+        let calc = CalcClient::new(sx.clone(), alloc.clone());
+        let info = Info::mock();
+        let bumped = calc.bump(info).await.expect("should call");
+        assert_eq!(bumped.version, info.version + 1);
+        assert_eq!(bumped.data, info.data);
+        let sum = calc.sum(info).await.expect("should call");
+        assert_eq!(*sum, info.version + info.data);
+
+        // The server does not know the method.
+        let other = OtherClient::new(sx.clone(), alloc.clone());
+        assert!(matches!(
+            other.bump(info).await,
+            Err(CallError::TypeMismatch { expected, got })
+                if expected == Info::TYPE_ID && got == <()>::TYPE_ID
+        ));
+        let shadow = shadow::CalcClient::new(sx, alloc);
+        assert!(matches!(
+            shadow.bump(info).await,
+            Err(CallError::TypeMismatch { expected, got })
+                if expected == Info::TYPE_ID && got == <()>::TYPE_ID
+        ));
+    };
+
+    let (served, _, ()) = tokio::join!(
+        serve(rr, rs, conn.alloc.clone(), CalcServer(Impl)),
+        cx.drive(),
+        client
+    );
+    assert_eq!(served, 4);
+}
+
+#[tokio::test]
+async fn conn_call() {
+    use crate::channel::driver::Identifier;
+    use crate::msg::TypeTag;
+    use crate::perlude::arena::channel::{CachePool, CallError, MsgToken, ReqNull};
+
+    const QSIZE: usize = 4;
+
+    const NAME: &str = "call";
+    const SIZE: usize = 60000;
+
+    let conn = mock_session::<ReqNull, 1>(NAME, SIZE);
+    let handle = conn.prepare(QSIZE).expect("alloc ok");
+    let view = conn.acquire(handle).expect("view ok");
+
+    let (ls, lr) = view.clone().lsplit().expect("should attach");
+    let (rs, rr) = view.clone().rsplit().expect("should attach");
+    let (sx, cx) = CachePool::<()>::new(QSIZE).bind(ls, lr, conn.alloc.clone());
+
+    let alloc = conn.alloc.clone();
+    // Echoes every request back.
+    let server = async move {
+        for _ in 0..2 {
+            let item = rr.recv().await.expect("should receive");
+            let (req, id): (MsgToken<()>, _) = item.decompose();
+            rs.send(Identifier::compose(req, id))
+                .await
+                .expect("should send");
+        }
+    };
+    let client = async move {
+        let info = Info::mock();
+        let echo = sx
+            .call::<Info, Info, _>(info, &alloc)
+            .await
+            .expect("should call");
+        assert_eq!(echo.version, info.version);
+        assert_eq!(echo.data, info.data);
+
+        // The response is freed if it is not of the expected type.
+        let mismatch = sx.call::<Info, u32, _>(info, &alloc).await;
+        assert!(matches!(
+            mismatch,
+            Err(CallError::TypeMismatch { expected, got })
+                if expected == u32::TYPE_ID && got == Info::TYPE_ID
+        ));
+    };
+
+    let (completed, (), ()) = tokio::join!(cx.drive(), server, client);
+    assert_eq!(completed, 2);
+}
@@ -29,4 +29,80 @@ fn alloc_content() {
     let a = mock_alloc(NAME, SIZE);
 
     tests::alloc_content::<BYTES_SIZE, ALLOC_NUM, NUM>(a);
-}
\ No newline at end of file
+}
+
+#[test]
+fn broadcast() {
+    use crate::channel::SendError;
+    use crate::perlude::talc::channel::{BroadcastTryRecvError as TryRecvError, MsgBroadcast};
+    use crate::reg::Registry;
+
+    const CAP: usize = 4;
+    const ROUNDS: u32 = 5000;
+
+    const NAME: &str = "broadcast";
+    const SIZE: usize = 60000;
+
+    let alloc = mock_alloc(NAME, SIZE);
+    let reg = Registry::<MsgBroadcast<()>, 1>::new();
+    let Ok((id, _)) = reg.prepare(CAP, &alloc) else {
+        panic!("alloc ok")
+    };
+    let (Some(view), _) = reg.peek(id, &alloc) else {
+        panic!("view ok")
+    };
+
+    let mut publisher = view.clone().publisher().expect("sole publisher");
+    assert!(view.clone().publisher().is_none());
+
+    // Nobody would read it, the token is handed back.
+    let (msg, _) = MoveMsg::new(0u32, &alloc);
+    let Err(SendError(token)) = publisher.try_send(msg.with(()), &alloc) else {
+        panic!("no subscriber")
+    };
+    MoveMsg::<u32>::detoken(token.unpack().0, &alloc).expect("should work");
+
+    let mut fast = view.clone().subscribe();
+    let mut slow = view.clone().subscribe();
+    assert_eq!(view.subscribers(), 2);
+
+    for i in 0..CAP as u32 + 2 {
+        let (msg, _) = MoveMsg::new(i, &alloc);
+        publisher
+            .try_send(msg.with(()), &alloc)
+            .expect("should send");
+        let shared = fast.try_recv().expect("should receive");
+        assert_eq!(shared.get::<u32, _>(&alloc), Some(&i));
+        shared.release(&alloc);
+    }
+    assert_eq!(fast.try_recv().err(), Some(TryRecvError::Empty));
+
+    // The slow subscriber lost the overwritten messages, the rest is still shared.
+    assert_eq!(slow.try_recv().err(), Some(TryRecvError::Lagged(2)));
+    for i in 2..CAP as u32 + 2 {
+        let shared = slow.try_recv().expect("should receive");
+        assert_eq!(shared.get::<u32, _>(&alloc), Some(&i));
+        shared.release(&alloc);
+    }
+
+    // Payloads skipped by a lagging subscriber are freed by the publisher.
+    for i in 0..ROUNDS {
+        let (msg, _) = MoveMsg::new(i, &alloc);
+        publisher
+            .try_send(msg.with(()), &alloc)
+            .expect("should send");
+        fast.try_recv().expect("should receive").release(&alloc);
+    }
+    assert!(matches!(slow.try_recv(), Err(TryRecvError::Lagged(_))));
+
+    drop(publisher);
+    while let Ok(shared) = slow.try_recv() {
+        shared.release(&alloc);
+    }
+    assert_eq!(slow.try_recv().err(), Some(TryRecvError::Disconnected));
+    assert_eq!(fast.try_recv().err(), Some(TryRecvError::Disconnected));
+
+    drop((fast, slow, view));
+    let _ = reg.clear(id, &alloc);
+    assert_eq!(reg.len(), 0);
+}
@@ -75,6 +75,13 @@ impl<T: ?Sized, M: Meta> TokenOf<T, M> {
         unsafe { NonNull::new_unchecked(ptr) }
     }
 
+    /// Resolves the pointer against the allocator base of this process.
+    #[inline]
+    pub unsafe fn recall(&self, base_ptr: *const u8) -> NonNull<T> {
+        let ptr = unsafe { self.metadata.as_ptr(self.meta.recall(base_ptr).as_ptr()) };
+        unsafe { NonNull::new_unchecked(ptr) }
+    }
+
     #[inline]
     pub unsafe fn detokenize<A: MemAlloc, H>(
         self,
@@ -101,6 +108,11 @@ impl<T: ?Sized + PointeeIn, M: Meta> TokenOf<T, M> {
     }
 }
 
+/// The payload type of cancel messages, which carry no data.
+const CANCEL_ID: TypeId = crate::msg::type_id::type_id("evering::Cancel");
+/// The payload type of credit messages, whose count rides in the metadata.
+const CREDIT_ID: TypeId = crate::msg::type_id::type_id("evering::Credit");
+
 pub struct Token<M: Meta> {
     meta: M,
     metadata: Metadata,
@@ -139,6 +151,46 @@ impl<M: Meta> Token<M> {
         }
     }
 
+    /// The empty payload of a cancel message, see [`Identified::cancel`].
+    #[inline]
+    fn cancel() -> Self {
+        Self {
+            id: CANCEL_ID,
+            ..Self::null()
+        }
+    }
+
+    /// Returns `true` if this is the payload of a cancel message.
+    #[inline]
+    pub fn is_cancel(&self) -> bool {
+        self.id == CANCEL_ID
+    }
+
+    /// The empty payload of a credit message, see [`Identified::credit`].
+    #[inline]
+    fn credit(n: u32) -> Self {
+        Self {
+            metadata: Metadata::Slice(n as usize),
+            id: CREDIT_ID,
+            ..Self::null()
+        }
+    }
+
+    /// Returns the granted count if this is the payload of a credit message.
+    #[inline]
+    pub fn credits(&self) -> Option<u32> {
+        match self.metadata {
+            Metadata::Slice(n) if self.id == CREDIT_ID => Some(n as u32),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the payload, see [`TypeTag::TYPE_ID`].
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.id
+    }
+
     #[inline(always)]
     pub fn with_default<H: Envelope + Default>(self) -> PackToken<H, M> {
         PackToken {
@@ -155,6 +207,26 @@ impl<M: Meta> Token<M> {
         }
     }
 
+    /// Resolves the payload in place if it is a `T`, leaving the token untouched.
+    #[inline]
+    pub fn as_ptr<T: Message + ?Sized, A: MemAlloc<Meta = M>>(
+        &self,
+        alloc: &A,
+    ) -> Option<NonNull<T>> {
+        (self.id == T::TYPE_ID).then(|| {
+            let ptr = unsafe { self.metadata.as_ptr(self.meta.recall_by(alloc).as_ptr()) };
+            unsafe { NonNull::new_unchecked(ptr) }
+        })
+    }
+
+    /// Frees the payload without knowing its type, its destructor is not run.
+    #[inline]
+    pub fn discard<A: MemAllocator<Meta = M>>(self, alloc: &A) {
+        if self.meta.layout_bytes().size() != 0 {
+            alloc.dealloc_bytes(self.meta);
+        }
+    }
+
     #[inline]
     pub fn identify<T: Message + ?Sized>(self) -> Option<TokenOf<T, M>> {
         (self.id == T::TYPE_ID).then_some(TokenOf {
@@ -238,9 +310,14 @@ impl<H: Envelope, M: Meta> PackToken<H, M> {
 }
 
 pub type ReqNull = ReqId<()>;
+#[derive(Debug)]
 pub struct ReqId<T: Envelope> {
     id: Id,
     header: T,
+    /// Set on the chunks of a streamed response but the last one.
+    more: bool,
+    /// The request linked to this one, set on the links of a chain but the last one.
+    next: Option<Id>,
 }
 
 impl<T: Envelope> const Deref for ReqId<T> {
@@ -274,8 +351,8 @@ impl<H: Tag<T>, T> Tag<T> for ReqId<H> {
         Self: Sized,
     {
         Self {
-            id: self.id,
             header: self.header.with_tag(value),
+            ..self
         }
     }
 
@@ -286,21 +363,86 @@ impl<H: Tag<T>, T> Tag<T> for ReqId<H> {
 }
 
 impl<T: Envelope> ReqId<T> {
+    #[inline]
+    const fn new(header: T, id: Id) -> Self {
+        Self {
+            id,
+            header,
+            more: false,
+            next: None,
+        }
+    }
+
     pub fn header(&self) -> &T {
         &self.header
     }
+
+    /// Returns `true` if more chunks of the same response follow.
+    pub fn more(&self) -> bool {
+        self.more
+    }
+
+    /// Returns the request linked to this one, served only once this one succeeded.
+    pub fn next(&self) -> Option<Id> {
+        self.next
+    }
 }
 
 impl<T: Envelope, M: Meta> Identified<ReqToken<T, M>> for PackToken<T, M> {
+    const CANCEL: bool = true;
+    const STREAM: bool = true;
+    const LINK: bool = true;
+
     fn compose(self, id: Id) -> ReqToken<T, M> {
         let (token, header) = self.unpack();
-        let header = ReqId { header, id };
-        token.with(header)
+        token.with(ReqId::new(header, id))
     }
 
     fn decompose(token: ReqToken<T, M>) -> (Self, Id) {
         let (token, header) = token.unpack();
-        let ReqId { id, header } = header;
+        let ReqId { id, header, .. } = header;
         (token.with(header), id)
     }
+
+    fn cancel(id: Id) -> Option<ReqToken<T, M>> {
+        T::control().map(|header| Token::cancel().with(ReqId::new(header, id)))
+    }
+
+    fn cancelled(token: &ReqToken<T, M>) -> Option<Id> {
+        token.token.is_cancel().then_some(token.header.id)
+    }
+
+    fn compose_more(self, id: Id) -> ReqToken<T, M> {
+        let (token, header) = self.unpack();
+        let header = ReqId {
+            more: true,
+            ..ReqId::new(header, id)
+        };
+        token.with(header)
+    }
+
+    fn is_end(token: &ReqToken<T, M>) -> bool {
+        !token.header.more
+    }
+
+    fn credit(id: Id, n: u32) -> Option<ReqToken<T, M>> {
+        T::control().map(|header| Token::credit(n).with(ReqId::new(header, id)))
+    }
+
+    fn credited(token: &ReqToken<T, M>) -> Option<(Id, u32)> {
+        token.token.credits().map(|n| (token.header.id, n))
+    }
+
+    fn compose_linked(self, id: Id, next: Id) -> ReqToken<T, M> {
+        let (token, header) = self.unpack();
+        let header = ReqId {
+            next: Some(next),
+            ..ReqId::new(header, id)
+        };
+        token.with(header)
+    }
+
+    fn linked(token: &ReqToken<T, M>) -> Option<Id> {
+        token.header.next
+    }
 }